@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::eval::Evaluator;
+use crate::game::Game;
+use crate::search::{Limits, Search, SearchResult};
+use crate::transposition::TranspositionTable;
+
+pub const DEFAULT_DEPTH: u32 = 5;
+
+// openings, middlegames and endgames; every position is searched from a clean table
+// to a fixed depth, so the node counts only change when the search itself does
+pub static POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "rnbqkb1r/pp2pppp/3p1n2/8/3NP3/8/PPP2PPP/RNBQKB1R w KQkq - 1 5",
+    "r2q1rk1/pp2bppp/2n1pn2/3p4/3P4/2NBPN2/PP3PPP/R2Q1RK1 w - - 0 10",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1",
+    "8/5pk1/6p1/8/8/6P1/5PK1/4R3 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+];
+
+// total nodes and time over the suite, reporting each position as it is done
+pub fn run (depth: u32, mut report: impl FnMut(&str, &SearchResult)) -> (u64, Duration) {
+    let start = Instant::now();
+    let mut nodes = 0;
+    let table = Arc::new(TranspositionTable::default());
+
+    for fen in POSITIONS {
+        let game = Game::from_fen(fen).expect("bench positions are valid");
+        table.clear();
+        let limits = Limits { depth: Some(depth), ..Limits::default() };
+        let result = Search::with_table(limits, Evaluator::default(), Arc::clone(&table)).run(&game);
+        nodes += result.nodes;
+        report(fen, &result);
+    }
+
+    (nodes, start.elapsed())
+}
@@ -0,0 +1,17 @@
+use std::env;
+use std::io;
+
+use chess_rs::uci::Uci;
+
+// run with "bench [depth]" as arguments to search the benchmark suite and exit
+fn main () -> io::Result<()> {
+    let mut engine = Uci::new(io::stdout());
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        engine.handle(&args.join(" "));
+        return Ok(());
+    }
+
+    engine.run(io::stdin().lock())
+}
@@ -0,0 +1,7 @@
+use std::io;
+
+use chess_rs::xboard::Xboard;
+
+fn main () -> io::Result<()> {
+    Xboard::new(io::stdout()).run(io::stdin().lock())
+}
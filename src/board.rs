@@ -1,71 +1,469 @@
-use std::ops::{Index, IndexMut};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Index;
 
+use crate::bitboard::{self, Bitboard};
+use crate::bitboard::{bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks};
 use crate::piece::pawn::PawnData;
-use crate::piece::{Color, Name, Piece, Position};
+use crate::piece::{bishop, king, knight, pawn, queen, rook};
+use crate::piece::{Color, Disambiguation, MoveMeta, Name, Piece, Position};
 use crate::utils::{all_positions, validate_pos};
+use crate::zobrist;
 
+// (king color, rook square) behind each castling right, in KQkq order
+pub static CASTLING_ROOKS: [(Color, Position); 4] = [
+    (Color::White, Position { row: 7, col: 7 }),
+    (Color::White, Position { row: 7, col: 0 }),
+    (Color::Black, Position { row: 0, col: 7 }),
+    (Color::Black, Position { row: 0, col: 0 }),
+];
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum PositionError {
+    MissingKing(Color),
+    ExtraKings(Color),
+    PawnOnBackRank(Position),
+    OpponentInCheck(Color),
+    InvalidCastling(Position),
+    InvalidPassant(Position),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::MissingKing(color) => write!(f, "{:?} has no king", color),
+            PositionError::ExtraKings(color) => write!(f, "{:?} has more than one king", color),
+            PositionError::PawnOnBackRank(pos) => write!(f, "pawn on the back rank at {}", pos),
+            PositionError::OpponentInCheck(color) => write!(f, "{:?} is in check but not to move", color),
+            PositionError::InvalidCastling(pos) => write!(f, "piece on {} cannot have castling rights", pos),
+            PositionError::InvalidPassant(pos) => write!(f, "en passant on {} is impossible", pos),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+// the pieces live in the mailbox, the bitboards mirror it for fast lookups
+// and are kept in sync by set and take, as is the placement part of the hash
 #[derive(Debug,Clone,Default)]
-pub struct Board(pub [[Option<Piece>; 8]; 8]);
+pub struct Board {
+    squares: [[Option<Piece>; 8]; 8],
+    pieces: [[Bitboard; 6]; 2],
+    colors: [Bitboard; 2],
+    hash: u64,
+}
+
+// two boards are the same position when pieces, castling rights and en passant agree
+impl PartialEq for Board {
+    fn eq (&self, other: &Board) -> bool {
+        self.pieces == other.pieces
+            && self.castling_rights() == other.castling_rights()
+            && self.passant_target() == other.passant_target()
+    }
+}
+
+impl Eq for Board {}
+
+impl Hash for Board {
+    fn hash<H: Hasher> (&self, state: &mut H) -> () {
+        state.write_u64(self.hash);
+    }
+}
 
 impl Index<usize> for Board {
     type Output = [Option<Piece>; 8];
 
     fn index(&self, row: usize) -> &Self::Output {
-        &self.0[row]
+        &self.squares[row]
     }
 }
 
-impl IndexMut<usize> for Board {
-    fn index_mut(&mut self, row: usize) -> &mut Self::Output {
-        &mut self.0[row]
+impl Board {
+    pub fn starting_position () -> Board {
+        let mut board = Board::default();
+
+        for (color, back_row, pawn_row) in [(Color::Black, 0, 1), (Color::White, 7, 6)] {
+            let back_rank: [fn(Position, Color) -> Piece; 8] = [
+                rook::new, knight::new, bishop::new, queen::new,
+                king::new, bishop::new, knight::new, rook::new,
+            ];
+
+            for (col, new_piece) in back_rank.into_iter().enumerate() {
+                let back_pos = Position { row: back_row, col };
+                let pawn_pos = Position { row: pawn_row, col };
+                board.set(back_row as i8, col as i8, Some(new_piece(back_pos, color)));
+                board.set(pawn_row as i8, col as i8, Some(pawn::new(pawn_pos, color)));
+            }
+        }
+        board.refresh_hash();
+
+        board
     }
-}
 
-impl Board {
     pub fn get (&self, row: i8, col: i8) -> Option<&Piece> {
         if !validate_pos(row, col) { return None; }
-        self[row as usize][col as usize].as_ref()
+        self.squares[row as usize][col as usize].as_ref()
     }
 
     pub fn get_mut (&mut self, row: i8, col: i8) -> Option<&mut Piece> {
         if !validate_pos(row, col) { return None; }
-        self[row as usize][col as usize].as_mut()
+        self.squares[row as usize][col as usize].as_mut()
     }
 
     pub fn take (&mut self, row: i8, col: i8) -> Option<Piece> {
         if !validate_pos(row, col) { return None; }
-        self[row as usize][col as usize].take()
+        let piece = self.squares[row as usize][col as usize].take()?;
+        self.toggle(&piece, row, col);
+
+        Some(piece)
     }
 
     pub fn set (&mut self, row: i8, col: i8, piece: Option<Piece>) -> () {
         if !validate_pos(row, col) { return; }
-        self[row as usize][col as usize] = piece;
+        self.take(row, col);
+        if let Some(p) = &piece {
+            self.toggle(p, row, col);
+        }
+        self.squares[row as usize][col as usize] = piece;
+    }
+
+    fn toggle (&mut self, piece: &Piece, row: i8, col: i8) -> () {
+        let pos = Position { row: row as usize, col: col as usize };
+        let bb = bitboard::bit(pos);
+        self.pieces[piece.color.index()][piece.name.index()] ^= bb;
+        self.colors[piece.color.index()] ^= bb;
+        self.hash ^= zobrist::piece(piece.name, piece.color, pos);
+    }
+
+    pub fn pieces (&self, name: Name, color: Color) -> Bitboard {
+        self.pieces[color.index()][name.index()]
+    }
+
+    pub fn occupancy (&self, color: Color) -> Bitboard {
+        self.colors[color.index()]
+    }
+
+    pub fn occupied (&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    // checks that the position could come up in a game with the given side to move,
+    // reporting every problem found rather than just the first
+    pub fn validate (&self, turn: Color) -> Result<(), Vec<PositionError>> {
+        let mut problems = Vec::new();
+
+        for color in [Color::White, Color::Black] {
+            match self.pieces(Name::King, color).count_ones() {
+                0 => problems.push(PositionError::MissingKing(color)),
+                1 => {},
+                _ => problems.push(PositionError::ExtraKings(color)),
+            }
+        }
+
+        let back_ranks = 0xFF | 0xFF << 56;
+        let pawns = self.pieces(Name::Pawn, Color::White) | self.pieces(Name::Pawn, Color::Black);
+        problems.extend(bitboard::positions(pawns & back_ranks).map(PositionError::PawnOnBackRank));
+
+        let waiting = turn.opposite();
+        if let Some(king_pos) = self.get_king_pos(waiting)
+            && self.is_attacked(king_pos, turn) {
+            problems.push(PositionError::OpponentInCheck(waiting));
+        }
+
+        // unmoved kings and rooks have to sit where castling starts from
+        for color in [Color::White, Color::Black] {
+            let unmoved = bitboard::positions(self.pieces(Name::King, color) | self.pieces(Name::Rook, color))
+                .filter_map(|pos| self.get(pos.row as i8, pos.col as i8))
+                .filter(|p| !p.data.has_moved());
+            for p in unmoved {
+                let in_place = match p.name {
+                    Name::King => p.pos.col == 4 && CASTLING_ROOKS.iter().any(|&(c, rook)| c == color && rook.row == p.pos.row),
+                    _ => CASTLING_ROOKS.contains(&(color, p.pos)),
+                };
+                if !in_place {
+                    problems.push(PositionError::InvalidCastling(p.pos));
+                }
+            }
+        }
+
+        if let Some(target) = self.passant_target()
+            && !self.passant_possible(target, turn) {
+            problems.push(PositionError::InvalidPassant(target));
+        }
+
+        if problems.is_empty() { Ok(()) } else { Err(problems) }
+    }
+
+    // the pawn that just made a double push has to be there, with both squares it crossed empty,
+    // and only pawns of the side to move may capture it
+    fn passant_possible (&self, target: Position, turn: Color) -> bool {
+        let (target_row, dir) = match turn {
+            Color::White => (2, 1),
+            Color::Black => (5, -1),
+        };
+        if target.row != target_row { return false; }
+
+        let pushed_row = target.row as i8 + dir;
+        let start_row = target.row as i8 - dir;
+        let pushed = self.get(pushed_row, target.col as i8)
+            .is_some_and(|p| p.name == Name::Pawn && p.color == turn.opposite());
+        let crossed_empty = self.is_empty_cell(target.row as i8, target.col as i8)
+            && self.is_empty_cell(start_row, target.col as i8);
+
+        let holders_valid = bitboard::positions(self.pieces(Name::Pawn, Color::White) | self.pieces(Name::Pawn, Color::Black))
+            .filter_map(|pos| self.get(pos.row as i8, pos.col as i8))
+            .filter(|p| p.data.as_any().downcast_ref::<PawnData>().is_some_and(|data| data.passant_target.is_some()))
+            .all(|p| p.color == turn && p.pos.row as i8 == pushed_row && p.pos.col.abs_diff(target.col) == 1);
+
+        pushed && crossed_empty && holders_valid
+    }
+
+    // every piece of the given color that attacks the square
+    pub fn attackers_of (&self, pos: Position, color: Color) -> Bitboard {
+        self.attackers_through(pos, color, self.occupied())
+    }
+
+    // attackers as if only the squares in occupied held pieces, sliders see through the rest;
+    // pieces taken off occupied still count as attackers, mask them out where that matters
+    pub fn attackers_through (&self, pos: Position, color: Color, occupied: Bitboard) -> Bitboard {
+        let diagonal = self.pieces(Name::Bishop, color) | self.pieces(Name::Queen, color);
+        let straight = self.pieces(Name::Rook, color) | self.pieces(Name::Queen, color);
+
+        // look outwards from the square with each piece's attack pattern
+        pawn_attacks(pos, color.opposite()) & self.pieces(Name::Pawn, color)
+            | knight_attacks(pos) & self.pieces(Name::Knight, color)
+            | king_attacks(pos) & self.pieces(Name::King, color)
+            | bishop_attacks(pos, occupied) & diagonal
+            | rook_attacks(pos, occupied) & straight
+    }
+
+    pub fn is_attacked (&self, pos: Position, by: Color) -> bool {
+        self.attackers_of(pos, by) != 0
+    }
+
+    // every square the given side attacks, whether or not it could legally move there
+    pub fn attack_map (&self, color: Color) -> Bitboard {
+        let occupied = self.occupied();
+
+        bitboard::positions(self.occupancy(color))
+            .filter_map(|pos| self.get(pos.row as i8, pos.col as i8))
+            .fold(0, |map, p| map | match p.name {
+                Name::Pawn => pawn_attacks(p.pos, color),
+                Name::Knight => knight_attacks(p.pos),
+                Name::King => king_attacks(p.pos),
+                Name::Bishop => bishop_attacks(p.pos, occupied),
+                Name::Rook => rook_attacks(p.pos, occupied),
+                Name::Queen => queen_attacks(p.pos, occupied),
+            })
     }
 
     pub fn is_enemy_cell (&self, row: i8, col: i8, color: Color) -> bool {
-        self.get(row, col).map_or(false, |p| p.color != color)
+        validate_pos(row, col) && self.occupancy(color.opposite()) & Self::cell(row, col) != 0
     }
 
     pub fn is_empty_cell (&self, row: i8, col: i8) -> bool {
-        self.get(row, col).is_none()
+        !validate_pos(row, col) || self.occupied() & Self::cell(row, col) == 0
+    }
+
+    fn cell (row: i8, col: i8) -> Bitboard {
+        bitboard::bit(Position { row: row as usize, col: col as usize })
     }
 
     pub fn get_king_pos (&self, color: Color) -> Option<Position> {
-        all_positions()
-            .find(|&pos| {
-                self.get(pos.row as i8, pos.col as i8)
-                    .map_or(false, |p| p.name == Name::King && p.color == color)
+        bitboard::positions(self.pieces(Name::King, color)).next()
+    }
+
+    // plays a move in place without any legality checks, those are up to the caller;
+    // the returned token is all unmake_move needs to put everything back
+    pub fn make_move (&mut self, from: Position, to: Position, promotion: Option<Name>) -> Option<MoveUndo> {
+        let (name, color, had_moved) = self.get(from.row as i8, from.col as i8)
+            .map(|p| (p.name, p.color, p.data.has_moved()))?;
+        let passant = self.passant_target();
+        let state = self.state_key();
+
+        // an en passant capture takes the pawn beside the mover, not the one on the target
+        let captured = if name == Name::Pawn && Some(to) == passant && self.is_empty_cell(to.row as i8, to.col as i8) {
+            self.take(from.row as i8, to.col as i8)
+        } else {
+            self.take(to.row as i8, to.col as i8)
+        };
+
+        let mut p = self.take(from.row as i8, from.col as i8)?;
+        let mut meta = p.on_move(to, self).unwrap_or(MoveMeta {
+            piece_name: name,
+            piece_color: color,
+            from,
+            to,
+            capture: false,
+            promotion: None,
+            castle: false,
+            check: false,
+            checkmate: false,
+            disambiguation: Disambiguation::None,
+        });
+        meta.capture = captured.is_some();
+
+        let pawn = promotion.map(|name| {
+            meta.promotion = Some(name);
+            let mut promoted = Piece::new(name, to, color);
+            // a promoted rook never brings castling rights with it
+            promoted.data.set_moved(true);
+            std::mem::replace(&mut p, promoted)
+        });
+        p.pos = to;
+        self.set(to.row as i8, to.col as i8, Some(p));
+        self.clear_passants(color);
+        self.drop_illegal_passants();
+        self.hash ^= state ^ self.state_key();
+
+        Some(MoveUndo { meta, captured, pawn, had_moved, passant })
+    }
+
+    pub fn unmake_move (&mut self, undo: MoveUndo) -> () {
+        let MoveUndo { meta, captured, pawn, had_moved, passant } = undo;
+        let (from, to) = (meta.from, meta.to);
+        let state = self.state_key();
+
+        if let Some(mut p) = self.take(to.row as i8, to.col as i8) {
+            if let Some(pawn) = pawn {
+                p = pawn;
+            }
+            p.pos = from;
+            p.data.set_moved(had_moved);
+            self.set(from.row as i8, from.col as i8, Some(p));
+        }
+
+        if meta.castle {
+            let rook_col = if to.col > from.col { 7 } else { 0 };
+            if let Some(mut rook) = self.take(from.row as i8, ((from.col + to.col) / 2) as i8) {
+                rook.pos = Position { row: from.row, col: rook_col };
+                rook.data.set_moved(false);
+                self.set(from.row as i8, rook_col as i8, Some(rook));
+            }
+        }
+
+        if let Some(captured) = captured {
+            self.set(captured.pos.row as i8, captured.pos.col as i8, Some(captured));
+        }
+
+        self.clear_passants(Color::White);
+        self.clear_passants(Color::Black);
+        if let Some(target) = passant {
+            self.restore_passant(target);
+            self.drop_illegal_passants();
+        }
+        self.hash ^= state ^ self.state_key();
+    }
+
+    // placement, castling rights and en passant file; the side to move is up to the game
+    pub fn hash (&self) -> u64 {
+        self.hash
+    }
+
+    // rebuilds the hash from scratch, needed after editing castling or en passant state by hand
+    pub fn refresh_hash (&mut self) -> () {
+        self.hash = all_positions()
+            .filter_map(|pos| self.get(pos.row as i8, pos.col as i8).map(|p| (pos, p)))
+            .fold(self.state_key(), |hash, (pos, p)| hash ^ zobrist::piece(p.name, p.color, pos));
+    }
+
+    fn state_key (&self) -> u64 {
+        zobrist::castling(self.castling_rights()) ^ zobrist::passant(self.passant_target())
+    }
+
+    // castling rights as bits in KQkq order, see CASTLING_ROOKS
+    pub fn castling_rights (&self) -> u8 {
+        CASTLING_ROOKS.iter()
+            .enumerate()
+            .filter(|&(_, &(color, rook_pos))| {
+                let king_ready = self.get(rook_pos.row as i8, 4)
+                    .filter(|p| p.name == Name::King && p.color == color)
+                    .is_some_and(|p| !p.data.has_moved());
+                let rook_ready = self.get(rook_pos.row as i8, rook_pos.col as i8)
+                    .filter(|p| p.name == Name::Rook && p.color == color)
+                    .is_some_and(|p| !p.data.has_moved());
+                king_ready && rook_ready
             })
+            .fold(0, |rights, (i, _)| rights | 1 << i)
+    }
+
+    // the square a pawn can currently be taken en passant on
+    pub fn passant_target (&self) -> Option<Position> {
+        [Color::White, Color::Black].into_iter()
+            .flat_map(|color| bitboard::positions(self.pieces(Name::Pawn, color)))
+            .filter_map(|pos| self.get(pos.row as i8, pos.col as i8))
+            .find_map(|p| p.data.as_any().downcast_ref::<PawnData>()?.passant_target)
+    }
+
+    // hands the target back to the pawns beside the one that made the double push
+    fn restore_passant (&mut self, target: Position) -> () {
+        let pushed_row = if target.row == 5 { 4 } else { 3 };
+        let pushed_color = match self.get(pushed_row, target.col as i8) {
+            Some(p) => p.color,
+            None => return,
+        };
+
+        for dc in [-1, 1] {
+            if let Some(p) = self.get_mut(pushed_row, target.col as i8 + dc)
+                && p.name == Name::Pawn && p.color != pushed_color
+                && let Some(pawn_data) = p.data.as_any_mut().downcast_mut::<PawnData>() {
+                pawn_data.passant_target = Some(target);
+            }
+        }
+    }
+
+    // takes the target away from every pawn whose capture would leave its own king in check,
+    // so a target is only part of the position, and the hash, when it can really be taken
+    pub fn drop_illegal_passants (&mut self) -> () {
+        let holders: Vec<(Position, Color, Position)> = bitboard::positions(self.pieces(Name::Pawn, Color::White) | self.pieces(Name::Pawn, Color::Black))
+            .filter_map(|pos| self.get(pos.row as i8, pos.col as i8))
+            .filter_map(|p| Some((p.pos, p.color, p.data.as_any().downcast_ref::<PawnData>()?.passant_target?)))
+            .collect();
+
+        for (from, color, target) in holders {
+            if !self.passant_legal(from, target, color)
+                && let Some(pawn_data) = self.get_mut(from.row as i8, from.col as i8)
+                    .and_then(|p| p.data.as_any_mut().downcast_mut::<PawnData>()) {
+                pawn_data.passant_target = None;
+            }
+        }
+    }
+
+    // the capture takes two pieces off the capturer's rank at once, which can open a line
+    // to the king that neither move alone would
+    fn passant_legal (&self, from: Position, target: Position, color: Color) -> bool {
+        // without a king there is nothing to expose
+        let Some(king) = self.get_king_pos(color) else {
+            return true;
+        };
+
+        let captured = bitboard::bit(Position { row: from.row, col: target.col });
+        let occupied = self.occupied() & !bitboard::bit(from) & !captured | bitboard::bit(target);
+        self.attackers_through(king, color.opposite(), occupied) & !captured == 0
     }
 
     pub fn clear_passants (&mut self, color: Color) -> () {
-        for pos in all_positions() {
-            if let Some(piece) = self.get_mut(pos.row as i8, pos.col as i8) {
-                if piece.color != color || piece.name != Name::Pawn { continue; }
-                if let Some(pawn_data) = piece.data.as_any_mut().downcast_mut::<PawnData>() {
-                    pawn_data.passant_target = None;
-                }
+        for pos in bitboard::positions(self.pieces(Name::Pawn, color)) {
+            if let Some(pawn_data) = self.get_mut(pos.row as i8, pos.col as i8)
+                .and_then(|p| p.data.as_any_mut().downcast_mut::<PawnData>()) {
+                pawn_data.passant_target = None;
             }
         }
     }
 }
+
+// everything make_move changed that can't be worked out again from the move itself
+#[derive(Debug,Clone)]
+pub struct MoveUndo {
+    meta: MoveMeta,
+    captured: Option<Piece>,
+    pawn: Option<Piece>,
+    had_moved: bool,
+    passant: Option<Position>,
+}
+
+impl MoveUndo {
+    pub fn meta (&self) -> MoveMeta {
+        self.meta
+    }
+}
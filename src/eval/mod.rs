@@ -0,0 +1,276 @@
+use std::fmt;
+
+use crate::bitboard::{self, Bitboard};
+use crate::board::Board;
+use crate::piece::{Color, Name, Position};
+
+mod pst;
+mod weights;
+
+pub use weights::{Score, Weights, WeightsError};
+
+// game phase with all pieces on the board, minor pieces count 1, rooks 2 and queens 4
+pub const PHASE_MAX: i32 = 24;
+
+const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+
+// centipawns; the king is never traded so it counts for nothing
+pub fn value (name: Name) -> i32 {
+    match name {
+        Name::Pawn => 100,
+        Name::Knight => 320,
+        Name::Bishop => 330,
+        Name::Rook => 500,
+        Name::Queen => 900,
+        Name::King => 0,
+    }
+}
+
+// score in centipawns for the given side with the default weights
+pub fn evaluate (board: &Board, color: Color) -> i32 {
+    Evaluator::default().evaluate(board, color)
+}
+
+pub fn explain (board: &Board, color: Color) -> Explanation {
+    Evaluator::default().explain(board, color)
+}
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum Term {
+    Material,
+    PieceSquares,
+    Mobility,
+    PawnStructure,
+    KingSafety,
+}
+
+impl Term {
+    pub const ALL: [Term; 5] = [Term::Material, Term::PieceSquares, Term::Mobility, Term::PawnStructure, Term::KingSafety];
+}
+
+impl fmt::Display for Term {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Term::Material => "material",
+            Term::PieceSquares => "piece-square",
+            Term::Mobility => "mobility",
+            Term::PawnStructure => "pawn structure",
+            Term::KingSafety => "king safety",
+        };
+        f.pad(label)
+    }
+}
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub struct TermScore {
+    pub term: Term,
+    // both phases before tapering
+    pub score: Score,
+    // what the term adds to the total
+    pub value: i32,
+}
+
+// how each term contributes, all from the point of view of color; the values sum to total
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub struct Explanation {
+    pub color: Color,
+    pub phase: i32,
+    pub terms: Vec<TermScore>,
+    pub total: i32,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16}{:>8}{:>8}{:>8}", "term", "mg", "eg", "value")?;
+        for term in &self.terms {
+            writeln!(f, "{:<16}{:>8}{:>8}{:>8}", term.term, term.score.mg, term.score.eg, term.value)?;
+        }
+        write!(f, "{:<16}{:>8}{:>8}{:>8}  ({:?}, phase {}/{})", "total", "", "", self.total, self.color, self.phase, PHASE_MAX)
+    }
+}
+
+#[derive(Debug,Clone,Default)]
+pub struct Evaluator {
+    weights: Weights,
+}
+
+impl Evaluator {
+    pub fn new (weights: Weights) -> Evaluator {
+        Evaluator { weights }
+    }
+
+    pub fn weights (&self) -> &Weights {
+        &self.weights
+    }
+
+    // centipawns, positive when color is better off
+    pub fn evaluate (&self, board: &Board, color: Color) -> i32 {
+        let phase = phase(board);
+        self.terms(board, color).iter().map(|score| score.taper(phase)).sum()
+    }
+
+    pub fn explain (&self, board: &Board, color: Color) -> Explanation {
+        let phase = phase(board);
+        let terms: Vec<TermScore> = Term::ALL.into_iter()
+            .zip(self.terms(board, color))
+            .map(|(term, score)| TermScore { term, score, value: score.taper(phase) })
+            .collect();
+        let total = terms.iter().map(|term| term.value).sum();
+
+        Explanation { color, phase, terms, total }
+    }
+
+    // in Term::ALL order, each one as color's share minus the opponent's
+    fn terms (&self, board: &Board, color: Color) -> [Score; 5] {
+        let side = |f: fn(&Evaluator, &Board, Color) -> Score| {
+            f(self, board, color) - f(self, board, color.opposite())
+        };
+
+        [
+            side(Evaluator::material),
+            side(Evaluator::piece_squares),
+            side(Evaluator::mobility),
+            side(Evaluator::pawn_structure),
+            side(Evaluator::king_safety),
+        ]
+    }
+
+    fn material (&self, board: &Board, color: Color) -> Score {
+        Name::ALL.iter()
+            .map(|&name| self.weights.material[name.index()] * board.pieces(name, color).count_ones() as i32)
+            .fold(Score::default(), |total, score| total + score)
+    }
+
+    fn piece_squares (&self, board: &Board, color: Color) -> Score {
+        let raw = Name::ALL.iter()
+            .flat_map(|&name| bitboard::positions(board.pieces(name, color)).map(move |pos| (name, pos)))
+            .map(|(name, pos)| {
+                let (mg, eg) = pst::lookup(name, color, pos);
+                Score::new(mg, eg)
+            })
+            .fold(Score::default(), |total, score| total + score);
+
+        let scale = self.weights.pst;
+        Score::new(raw.mg * scale.mg / 100, raw.eg * scale.eg / 100)
+    }
+
+    // the same squares the pieces' legal_moves give, counted straight off the attack tables;
+    // pins, checks and castling are left out
+    fn mobility (&self, board: &Board, color: Color) -> Score {
+        let occupied = board.occupied();
+        let free = !board.occupancy(color);
+        let mut total = Score::default();
+
+        for name in Name::ALL {
+            for pos in bitboard::positions(board.pieces(name, color)) {
+                let moves = match name {
+                    Name::Pawn => pawn_moves(board, pos, color),
+                    Name::Knight => bitboard::knight_attacks(pos) & free,
+                    Name::Bishop => bitboard::bishop_attacks(pos, occupied) & free,
+                    Name::Rook => bitboard::rook_attacks(pos, occupied) & free,
+                    Name::Queen => bitboard::queen_attacks(pos, occupied) & free,
+                    Name::King => bitboard::king_attacks(pos) & free,
+                };
+                total += self.weights.mobility[name.index()] * moves.count_ones() as i32;
+            }
+        }
+
+        total
+    }
+
+    fn pawn_structure (&self, board: &Board, color: Color) -> Score {
+        let pawns = board.pieces(Name::Pawn, color);
+        let enemy = board.pieces(Name::Pawn, color.opposite());
+        let mut total = Score::default();
+
+        for col in 0..8 {
+            let on_file = (pawns & file(col)).count_ones() as i32;
+            if on_file > 1 {
+                total += self.weights.doubled_pawn * (on_file - 1);
+            }
+            if on_file > 0 && pawns & neighbour_files(col) == 0 {
+                total += self.weights.isolated_pawn * on_file;
+            }
+        }
+
+        for pos in bitboard::positions(pawns) {
+            let span = ahead(pos.row, color) & (file(pos.col) | neighbour_files(pos.col));
+            if enemy & span != 0 { continue; }
+
+            let advanced = match color {
+                Color::White => 6 - pos.row as i32,
+                Color::Black => pos.row as i32 - 1,
+            };
+            total += self.weights.passed_pawn * advanced.max(0);
+        }
+
+        total
+    }
+
+    fn king_safety (&self, board: &Board, color: Color) -> Score {
+        let Some(king) = board.get_king_pos(color) else {
+            return Score::default();
+        };
+
+        // the two ranks in front of the king on its own and the neighbouring files
+        let forward = match color {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        let shield = (-1..=1)
+            .flat_map(|dx| (1..=2).filter_map(move |dy| king.shifted(dx, forward * dy)))
+            .fold(0, |bb, pos| bb | bitboard::bit(pos));
+        let shelter = (shield & board.pieces(Name::Pawn, color)).count_ones() as i32;
+
+        let zone = bitboard::king_attacks(king);
+        let attacked = (zone & board.attack_map(color.opposite())).count_ones() as i32;
+
+        self.weights.king_shield * shelter + self.weights.king_attack * attacked
+    }
+}
+
+fn phase (board: &Board) -> i32 {
+    let count = |name| {
+        (board.pieces(name, Color::White) | board.pieces(name, Color::Black)).count_ones() as i32
+    };
+
+    let phase = count(Name::Knight) + count(Name::Bishop) + 2 * count(Name::Rook) + 4 * count(Name::Queen);
+    phase.min(PHASE_MAX)
+}
+
+// pushes onto empty squares, two from the starting rank, and captures of enemy pieces
+fn pawn_moves (board: &Board, pos: Position, color: Color) -> Bitboard {
+    let (forward, start) = match color {
+        Color::White => (-1, 6),
+        Color::Black => (1, 1),
+    };
+    let empty = !board.occupied();
+    let mut moves = bitboard::pawn_attacks(pos, color) & board.occupancy(color.opposite());
+
+    if let Some(one) = pos.shifted(0, forward) && empty & bitboard::bit(one) != 0 {
+        moves |= bitboard::bit(one);
+        if pos.row == start && let Some(two) = one.shifted(0, forward) {
+            moves |= bitboard::bit(two) & empty;
+        }
+    }
+
+    moves
+}
+
+fn file (col: usize) -> Bitboard {
+    FILE_A << col
+}
+
+fn neighbour_files (col: usize) -> Bitboard {
+    let left = if col > 0 { file(col - 1) } else { 0 };
+    let right = if col < 7 { file(col + 1) } else { 0 };
+    left | right
+}
+
+// every square on the rows in front of the given row, as seen by color
+fn ahead (row: usize, color: Color) -> Bitboard {
+    match color {
+        Color::White => (1 << (row * 8)) - 1,
+        Color::Black => u64::MAX.checked_shl((row as u32 + 1) * 8).unwrap_or(0),
+    }
+}
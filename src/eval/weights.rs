@@ -0,0 +1,224 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+use std::path::Path;
+
+use crate::piece::Name;
+
+// a middlegame and an endgame value, blended by how much material is left
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Default)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const fn new (mg: i32, eg: i32) -> Score {
+        Score { mg, eg }
+    }
+
+    // phase runs from 0 for bare kings up to PHASE_MAX for the full set of pieces
+    pub fn taper (&self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (super::PHASE_MAX - phase)) / super::PHASE_MAX
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+    fn add (self, other: Score) -> Score {
+        Score::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign (&mut self, other: Score) -> () {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+    fn sub (self, other: Score) -> Score {
+        Score::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+    fn neg (self) -> Score {
+        Score::new(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+    fn mul (self, n: i32) -> Score {
+        Score::new(self.mg * n, self.eg * n)
+    }
+}
+
+#[derive(Debug)]
+pub enum WeightsError {
+    Io(io::Error),
+    Syntax { line: usize, text: String },
+    UnknownKey { line: usize, key: String },
+    InvalidValue { line: usize, value: String },
+}
+
+impl fmt::Display for WeightsError {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightsError::Io(err) => write!(f, "read failed: {}", err),
+            WeightsError::Syntax { line, text } => write!(f, "line {}: expected 'key = value', got '{}'", line, text),
+            WeightsError::UnknownKey { line, key } => write!(f, "line {}: unknown weight '{}'", line, key),
+            WeightsError::InvalidValue { line, value } => write!(f, "line {}: expected one or two integers, got '{}'", line, value),
+        }
+    }
+}
+
+impl std::error::Error for WeightsError {}
+
+impl From<io::Error> for WeightsError {
+    fn from (err: io::Error) -> WeightsError {
+        WeightsError::Io(err)
+    }
+}
+
+// everything the evaluation multiplies by, in centipawns; penalties are negative
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub struct Weights {
+    // indexed by Name::index
+    pub material: [Score; 6],
+    // per square a piece can move to
+    pub mobility: [Score; 6],
+    // percentage applied to the piece-square tables
+    pub pst: Score,
+    pub doubled_pawn: Score,
+    pub isolated_pawn: Score,
+    // per rank the pawn has advanced
+    pub passed_pawn: Score,
+    // per own pawn in front of the king
+    pub king_shield: Score,
+    // per square around the king the opponent attacks
+    pub king_attack: Score,
+}
+
+impl Default for Weights {
+    fn default () -> Weights {
+        let mut material = [Score::default(); 6];
+        let mut mobility = [Score::default(); 6];
+        for (name, value, moves) in [
+            (Name::Pawn, Score::new(100, 120), Score::new(0, 0)),
+            (Name::Knight, Score::new(320, 300), Score::new(4, 4)),
+            (Name::Bishop, Score::new(330, 320), Score::new(5, 5)),
+            (Name::Rook, Score::new(500, 520), Score::new(2, 4)),
+            (Name::Queen, Score::new(900, 920), Score::new(1, 2)),
+            (Name::King, Score::new(0, 0), Score::new(0, 0)),
+        ] {
+            material[name.index()] = value;
+            mobility[name.index()] = moves;
+        }
+
+        Weights {
+            material,
+            mobility,
+            pst: Score::new(100, 100),
+            doubled_pawn: Score::new(-10, -20),
+            isolated_pawn: Score::new(-10, -15),
+            passed_pawn: Score::new(5, 15),
+            king_shield: Score::new(10, 0),
+            king_attack: Score::new(-8, 0),
+        }
+    }
+}
+
+impl Weights {
+    pub fn from_file<P: AsRef<Path>> (path: P) -> Result<Weights, WeightsError> {
+        Weights::parse(&fs::read_to_string(path)?)
+    }
+
+    // one 'key = mg eg' per line, a single value sets both phases;
+    // keys left out keep their default and # starts a comment
+    pub fn parse (config: &str) -> Result<Weights, WeightsError> {
+        let mut weights = Weights::default();
+
+        for (i, raw) in config.lines().enumerate() {
+            let line = i + 1;
+            let text = raw.split('#').next().unwrap_or("").trim();
+            if text.is_empty() { continue; }
+
+            let Some((key, value)) = text.split_once('=') else {
+                return Err(WeightsError::Syntax { line, text: text.to_string() });
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            let score = parse_score(value)
+                .ok_or_else(|| WeightsError::InvalidValue { line, value: value.to_string() })?;
+            let slot = weights.slot(key)
+                .ok_or_else(|| WeightsError::UnknownKey { line, key: key.to_string() })?;
+            *slot = score;
+        }
+
+        Ok(weights)
+    }
+
+    fn slot (&mut self, key: &str) -> Option<&mut Score> {
+        if let Some((table, piece)) = key.split_once('.') {
+            let name = Name::ALL.into_iter().find(|&name| piece_key(name) == piece)?;
+            return match table {
+                "material" => Some(&mut self.material[name.index()]),
+                "mobility" => Some(&mut self.mobility[name.index()]),
+                _ => None,
+            };
+        }
+
+        match key {
+            "pst" => Some(&mut self.pst),
+            "doubled_pawn" => Some(&mut self.doubled_pawn),
+            "isolated_pawn" => Some(&mut self.isolated_pawn),
+            "passed_pawn" => Some(&mut self.passed_pawn),
+            "king_shield" => Some(&mut self.king_shield),
+            "king_attack" => Some(&mut self.king_attack),
+            _ => None,
+        }
+    }
+}
+
+// writes a config that parses back to the same weights
+impl fmt::Display for Weights {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = |f: &mut fmt::Formatter<'_>, key: &str, score: Score| {
+            writeln!(f, "{} = {} {}", key, score.mg, score.eg)
+        };
+
+        for name in Name::ALL {
+            line(f, &format!("material.{}", piece_key(name)), self.material[name.index()])?;
+        }
+        for name in Name::ALL {
+            line(f, &format!("mobility.{}", piece_key(name)), self.mobility[name.index()])?;
+        }
+        line(f, "pst", self.pst)?;
+        line(f, "doubled_pawn", self.doubled_pawn)?;
+        line(f, "isolated_pawn", self.isolated_pawn)?;
+        line(f, "passed_pawn", self.passed_pawn)?;
+        line(f, "king_shield", self.king_shield)?;
+        line(f, "king_attack", self.king_attack)
+    }
+}
+
+fn piece_key (name: Name) -> String {
+    format!("{:?}", name).to_lowercase()
+}
+
+fn parse_score (value: &str) -> Option<Score> {
+    let parts = value.split_whitespace()
+        .map(|part| part.parse::<i32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    match parts[..] {
+        [both] => Some(Score::new(both, both)),
+        [mg, eg] => Some(Score::new(mg, eg)),
+        _ => None,
+    }
+}
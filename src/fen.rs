@@ -0,0 +1,294 @@
+use std::fmt;
+
+use crate::board::{Board, CASTLING_ROOKS};
+use crate::piece::king::KingData;
+use crate::piece::pawn::PawnData;
+use crate::piece::rook::RookData;
+use crate::piece::{Color, Name, Piece, Position};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub enum FenError {
+    FieldCount(usize),
+    RankCount(usize),
+    RankLength(usize),
+    InvalidPiece(char),
+    InvalidTurn(String),
+    InvalidCastling(String),
+    InvalidPassant(String),
+    InvalidHalfmove(String),
+    InvalidFullmove(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "expected 6 fields, found {}", n),
+            FenError::RankCount(n) => write!(f, "expected 8 ranks, found {}", n),
+            FenError::RankLength(rank) => write!(f, "rank {} does not describe 8 squares", rank),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece character '{}'", c),
+            FenError::InvalidTurn(s) => write!(f, "invalid side to move '{}'", s),
+            FenError::InvalidCastling(s) => write!(f, "invalid castling rights '{}'", s),
+            FenError::InvalidPassant(s) => write!(f, "invalid en passant square '{}'", s),
+            FenError::InvalidHalfmove(s) => write!(f, "invalid halfmove clock '{}'", s),
+            FenError::InvalidFullmove(s) => write!(f, "invalid fullmove number '{}'", s),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+pub struct FenPosition {
+    pub board: Board,
+    pub turn: Color,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+pub fn parse (fen: &str) -> Result<FenPosition, FenError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() != 6 {
+        return Err(FenError::FieldCount(fields.len()));
+    }
+
+    let mut board = parse_placement(fields[0])?;
+
+    let turn = match fields[1] {
+        "w" => Color::White,
+        "b" => Color::Black,
+        other => return Err(FenError::InvalidTurn(other.to_string())),
+    };
+
+    apply_castling(&mut board, fields[2])?;
+    apply_passant(&mut board, fields[3], turn)?;
+    board.refresh_hash();
+
+    let halfmove_clock = fields[4].parse::<u32>()
+        .map_err(|_| FenError::InvalidHalfmove(fields[4].to_string()))?;
+    let fullmove_number = match fields[5].parse::<u32>() {
+        Ok(n) if n > 0 => n,
+        _ => return Err(FenError::InvalidFullmove(fields[5].to_string())),
+    };
+
+    Ok(FenPosition { board, turn, halfmove_clock, fullmove_number })
+}
+
+pub fn write (board: &Board, turn: Color, halfmove_clock: u32, fullmove_number: u32) -> String {
+    format!("{} {} {}", write_position(board, turn), halfmove_clock, fullmove_number)
+}
+
+// the first four fields, which identify a position
+pub fn write_position (board: &Board, turn: Color) -> String {
+    let turn = match turn {
+        Color::White => 'w',
+        Color::Black => 'b',
+    };
+
+    format!(
+        "{} {} {} {}",
+        write_placement(board),
+        turn,
+        write_castling(board),
+        write_passant(board),
+    )
+}
+
+fn parse_placement (field: &str) -> Result<Board, FenError> {
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::RankCount(ranks.len()));
+    }
+
+    let mut board = Board::default();
+
+    for (row, rank) in ranks.iter().enumerate() {
+        let mut col = 0;
+
+        for c in rank.chars() {
+            if let Some(skip) = c.to_digit(10) {
+                if skip == 0 || skip > 8 {
+                    return Err(FenError::InvalidPiece(c));
+                }
+                col += skip as usize;
+                continue;
+            }
+
+            if col >= 8 {
+                return Err(FenError::RankLength(8 - row));
+            }
+
+            let pos = Position { row, col };
+            board.set(row as i8, col as i8, Some(piece_from_char(c, pos)?));
+            col += 1;
+        }
+
+        if col != 8 {
+            return Err(FenError::RankLength(8 - row));
+        }
+    }
+
+    Ok(board)
+}
+
+fn piece_from_char (c: char, pos: Position) -> Result<Piece, FenError> {
+    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+
+    let name = Name::from_letter(c.to_ascii_uppercase()).ok_or(FenError::InvalidPiece(c))?;
+    let mut piece = Piece::new(name, pos, color);
+
+    // castling rights are granted afterwards, everything starts out as moved
+    if let Some(data) = piece.data.as_any_mut().downcast_mut::<KingData>() {
+        data.has_moved = true;
+    }
+    if let Some(data) = piece.data.as_any_mut().downcast_mut::<RookData>() {
+        data.has_moved = true;
+    }
+    if let Some(data) = piece.data.as_any_mut().downcast_mut::<PawnData>() {
+        data.has_moved = pos.row != start_row(color);
+    }
+
+    Ok(piece)
+}
+
+fn piece_to_char (piece: &Piece) -> char {
+    match piece.color {
+        Color::White => piece.name.letter(),
+        Color::Black => piece.name.letter().to_ascii_lowercase(),
+    }
+}
+
+fn write_placement (board: &Board) -> String {
+    let mut placement = String::new();
+
+    for row in 0..8 {
+        let mut empty = 0;
+
+        for col in 0..8 {
+            match board.get(row, col) {
+                Some(p) => {
+                    if empty > 0 {
+                        placement.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    placement.push(piece_to_char(p));
+                }
+                None => empty += 1,
+            }
+        }
+
+        if empty > 0 {
+            placement.push_str(&empty.to_string());
+        }
+        if row < 7 {
+            placement.push('/');
+        }
+    }
+
+    placement
+}
+
+fn apply_castling (board: &mut Board, field: &str) -> Result<(), FenError> {
+    if field == "-" {
+        return Ok(());
+    }
+
+    let invalid = || FenError::InvalidCastling(field.to_string());
+
+    for c in field.chars() {
+        let &(color, rook_pos) = CASTLING_ROOKS.iter()
+            .find(|&&(color, rook_pos)| castling_char(color, rook_pos) == c)
+            .ok_or_else(invalid)?;
+
+        let king_row = back_row(color) as i8;
+        let king = board.get_mut(king_row, 4)
+            .filter(|p| p.name == Name::King && p.color == color)
+            .and_then(|p| p.data.as_any_mut().downcast_mut::<KingData>())
+            .ok_or_else(invalid)?;
+        king.has_moved = false;
+
+        let rook = board.get_mut(rook_pos.row as i8, rook_pos.col as i8)
+            .filter(|p| p.name == Name::Rook && p.color == color)
+            .and_then(|p| p.data.as_any_mut().downcast_mut::<RookData>())
+            .ok_or_else(invalid)?;
+        rook.has_moved = false;
+    }
+
+    Ok(())
+}
+
+fn write_castling (board: &Board) -> String {
+    let bits = board.castling_rights();
+    let rights: String = CASTLING_ROOKS.iter()
+        .enumerate()
+        .filter(|&(i, _)| bits & 1 << i != 0)
+        .map(|(_, &(color, rook_pos))| castling_char(color, rook_pos))
+        .collect();
+
+    if rights.is_empty() { String::from("-") } else { rights }
+}
+
+// K or Q by the side the rook starts on, lowercase for black
+fn castling_char (color: Color, rook_pos: Position) -> char {
+    let side = if rook_pos.col == 7 { 'k' } else { 'q' };
+    match color {
+        Color::White => side.to_ascii_uppercase(),
+        Color::Black => side,
+    }
+}
+
+fn apply_passant (board: &mut Board, field: &str, turn: Color) -> Result<(), FenError> {
+    if field == "-" {
+        return Ok(());
+    }
+
+    let invalid = || FenError::InvalidPassant(field.to_string());
+    let target = Position::from_algebraic(field).ok_or_else(invalid)?;
+
+    // the pawn that just made a double push sits one row past the target
+    let (target_row, pushed_row) = match turn {
+        Color::White => (2, 3),
+        Color::Black => (5, 4),
+    };
+    if target.row != target_row {
+        return Err(invalid());
+    }
+
+    // a target no pawn can legally take is accepted but not kept, see write_passant
+    let pushed_color = turn.opposite();
+    board.get(pushed_row, target.col as i8)
+        .filter(|p| p.name == Name::Pawn && p.color == pushed_color)
+        .ok_or_else(invalid)?;
+
+    for dc in [-1, 1] {
+        if let Some(p) = board.get_mut(pushed_row, target.col as i8 + dc)
+            && p.name == Name::Pawn && p.color == turn
+            && let Some(data) = p.data.as_any_mut().downcast_mut::<PawnData>() {
+            data.passant_target = Some(target);
+        }
+    }
+    board.drop_illegal_passants();
+
+    Ok(())
+}
+
+// the target is only written when a pawn can legally take there, as it is only then
+// part of the position; a FEN read with a target nobody can use comes back with "-"
+fn write_passant (board: &Board) -> String {
+    board.passant_target()
+        .map_or_else(|| String::from("-"), |target| target.to_string())
+}
+
+fn back_row (color: Color) -> usize {
+    match color {
+        Color::White => 7,
+        Color::Black => 0,
+    }
+}
+
+fn start_row (color: Color) -> usize {
+    match color {
+        Color::White => 6,
+        Color::Black => 1,
+    }
+}
@@ -1,15 +1,178 @@
-use crate::board::Board;
-use crate::piece::{Color, MoveMeta, Position};
-use crate::rules::filter_moves;
+use std::fmt;
 
-#[derive(Default)]
+use crate::bitboard::positions;
+use crate::board::{Board, MoveUndo, PositionError};
+use crate::fen::{self, FenError, STARTING_FEN};
+use crate::pgn::{PgnError, PgnGame};
+use crate::lan::{self, LanError};
+use crate::perft;
+use crate::san::{self, SanError};
+use crate::search::{Limits, Search, SearchResult};
+use crate::piece::{pawn, Color, Disambiguation, Move, MoveMeta, Name, Position};
+use crate::rules::{self, filter_moves, has_legal_moves, is_in_check, is_insufficient_material};
+use crate::zobrist;
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum MoveError {
+    NoPieceAtSource,
+    NotYourTurn,
+    IllegalMove,
+    GameOver,
+    PromotionRequired,
+    InvalidPromotion(Name),
+    NoDrawToClaim,
+    InvalidPosition,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoPieceAtSource => write!(f, "no piece on the source square"),
+            MoveError::NotYourTurn => write!(f, "piece does not belong to the side to move"),
+            MoveError::IllegalMove => write!(f, "illegal move"),
+            MoveError::GameOver => write!(f, "game is already over"),
+            MoveError::PromotionRequired => write!(f, "move requires a promotion piece"),
+            MoveError::InvalidPromotion(name) => write!(f, "cannot promote to {:?} here", name),
+            MoveError::NoDrawToClaim => write!(f, "no draw can be claimed in this position"),
+            MoveError::InvalidPosition => write!(f, "position is not valid"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum DrawReason {
+    Agreement,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    InsufficientMaterial,
+}
+
+// the color carried by terminal variants is the winner
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Default)]
+pub enum GameStatus {
+    #[default]
+    Ongoing,
+    Check,
+    Checkmate(Color),
+    Stalemate,
+    Draw(DrawReason),
+    Resigned(Color),
+    TimeForfeit(Color),
+    // the board fails Board::validate, nothing can be played from it
+    Invalid,
+}
+
+impl GameStatus {
+    pub fn is_over (&self) -> bool {
+        !matches!(self, GameStatus::Ongoing | GameStatus::Check | GameStatus::Invalid)
+    }
+
+    // there are moves to be played, neither is the game over nor is the board invalid
+    pub fn is_playable (&self) -> bool {
+        matches!(self, GameStatus::Ongoing | GameStatus::Check)
+    }
+}
+
+// everything a move destroys, kept so that it can be taken back
+#[derive(Debug,Clone)]
+struct UndoRecord {
+    undo: MoveUndo,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    status: GameStatus,
+}
+
+#[derive(Clone)]
 pub struct Game {
     pub board: Board,
-    pub history: Vec<MoveMeta>,
+    history: Vec<MoveMeta>,
     turn: Color,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    status: GameStatus,
+    undo_stack: Vec<UndoRecord>,
+    redo_stack: Vec<MoveMeta>,
+    positions: Vec<u64>,
+    start_fen: String,
+}
+
+// an empty board, which has no kings and so is an invalid position
+impl Default for Game {
+    fn default () -> Game {
+        let mut game = Game::blank();
+        game.positions.push(game.hash());
+        game.update_status();
+
+        game
+    }
 }
 
 impl Game {
+    pub fn new () -> Game {
+        let mut game = Game {
+            board: Board::starting_position(),
+            start_fen: STARTING_FEN.to_string(),
+            ..Game::blank()
+        };
+        game.positions.push(game.hash());
+
+        game
+    }
+
+    pub fn from_fen (fen: &str) -> Result<Game, FenError> {
+        let position = fen::parse(fen)?;
+
+        let mut game = Game {
+            board: position.board,
+            history: Vec::new(),
+            turn: position.turn,
+            halfmove_clock: position.halfmove_clock,
+            fullmove_number: position.fullmove_number,
+            start_fen: fen.to_string(),
+            ..Game::blank()
+        };
+        game.positions.push(game.hash());
+        game.update_status();
+
+        Ok(game)
+    }
+
+    // every field empty, for the constructors to fill in
+    fn blank () -> Game {
+        Game {
+            board: Board::default(),
+            history: Vec::new(),
+            turn: Color::default(),
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            status: GameStatus::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            positions: Vec::new(),
+            start_fen: String::new(),
+        }
+    }
+
+    pub fn to_fen (&self) -> String {
+        fen::write(&self.board, self.turn, self.halfmove_clock, self.fullmove_number)
+    }
+
+    pub fn start_fen (&self) -> &str {
+        &self.start_fen
+    }
+
+    pub fn from_pgn (pgn: &str) -> Result<Game, PgnError> {
+        PgnGame::parse(pgn)?.to_game()
+    }
+
+    pub fn to_pgn (&self, tags: &[(&str, &str)]) -> String {
+        PgnGame::from_game(self, tags).to_string()
+    }
+
     fn switch_turn (&mut self) -> () {
         self.turn = self.turn.opposite();
     }
@@ -18,44 +181,294 @@ impl Game {
         self.turn
     }
 
-    pub fn apply_move (&mut self, from: Position, to: Position) -> () {
-        let piece = match self.board.get(from.row as i8, from.col as i8) {
-            Some(p) => p,
-            None => return,
-        };
+    pub fn validate (&self) -> Result<(), Vec<PositionError>> {
+        self.board.validate(self.turn)
+    }
+
+    pub fn status (&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn resign (&mut self, color: Color) -> Result<GameStatus, MoveError> {
+        self.finish(GameStatus::Resigned(color.opposite()))
+    }
+
+    pub fn time_forfeit (&mut self, color: Color) -> Result<GameStatus, MoveError> {
+        self.finish(GameStatus::TimeForfeit(color.opposite()))
+    }
+
+    pub fn agree_draw (&mut self) -> Result<GameStatus, MoveError> {
+        self.finish(GameStatus::Draw(DrawReason::Agreement))
+    }
+
+    pub fn claimable_draw (&self) -> Option<DrawReason> {
+        if !self.status.is_playable() {
+            None
+        } else if self.repetitions() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    pub fn claim_draw (&mut self) -> Result<GameStatus, MoveError> {
+        if self.status.is_over() {
+            return Err(MoveError::GameOver);
+        }
+
+        let reason = self.claimable_draw().ok_or(MoveError::NoDrawToClaim)?;
+        self.finish(GameStatus::Draw(reason))
+    }
+
+    // zobrist key of the board plus the side to move
+    pub fn hash (&self) -> u64 {
+        match self.turn {
+            Color::White => self.board.hash(),
+            Color::Black => self.board.hash() ^ zobrist::side(),
+        }
+    }
 
-        if self.turn != piece.color { return; }
+    fn repetitions (&self) -> usize {
+        self.positions.last()
+            .map_or(0, |current| self.positions.iter().filter(|&key| key == current).count())
+    }
 
-        let mut legal_moves = piece.legal_moves(&self.board);
-        filter_moves(&self.board, &mut legal_moves, from, piece.color);
+    fn finish (&mut self, status: GameStatus) -> Result<GameStatus, MoveError> {
+        if self.status.is_over() {
+            return Err(MoveError::GameOver);
+        }
 
-        if !legal_moves.contains(&to) {
-            println!("Log: illegal move");
-            println!("Info: from - {:?}, to - {:?}, piece - {:?}", from, to, piece);
+        self.status = status;
+        Ok(status)
+    }
+
+    fn update_status (&mut self) -> () {
+        if self.board.validate(self.turn).is_err() {
+            self.status = GameStatus::Invalid;
             return;
         }
 
-        let mut p = match self.board.take(from.row as i8, from.col as i8) {
-            Some(p) => p,
-            None => return,
+        let in_check = is_in_check(&self.board, self.turn) == Ok(true);
+        let can_move = has_legal_moves(&mut self.board, self.turn);
+
+        self.status = match (in_check, can_move) {
+            (true, false) => GameStatus::Checkmate(self.turn.opposite()),
+            (false, false) => GameStatus::Stalemate,
+            _ if self.repetitions() >= 5 => GameStatus::Draw(DrawReason::FivefoldRepetition),
+            _ if self.halfmove_clock >= 150 => GameStatus::Draw(DrawReason::SeventyFiveMoveRule),
+            _ if is_insufficient_material(&self.board) => GameStatus::Draw(DrawReason::InsufficientMaterial),
+            (true, true) => GameStatus::Check,
+            (false, true) => GameStatus::Ongoing,
         };
+    }
 
-        p.pos = to;
-        if let Some(move_meta) = p.on_move(to, &mut self.board) {
-            self.history.push(move_meta)
-        } else {
-            self.history.push(MoveMeta {
-                piece_name: p.name,
-                piece_color: p.color,
-                from,
-                to,
-                capture: self.board.get(to.row as i8, to.col as i8).is_some(),
-                promotion: None,
-                castle: false
-            })
-        }
-        self.board.set(to.row as i8, to.col as i8, Some(p));
+    pub fn get_halfmove_clock (&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn get_fullmove_number (&self) -> u32 {
+        self.fullmove_number
+    }
+
+    // the moves played so far, oldest first
+    pub fn history (&self) -> &[MoveMeta] {
+        &self.history
+    }
+
+    // hashes of every position so far, the current one last
+    pub fn position_history (&self) -> &[u64] {
+        &self.positions
+    }
+
+    // with a table of its own, so nothing is remembered from one call to the next
+    pub fn search (&self, limits: Limits) -> SearchResult {
+        Search::new(limits).run(self)
+    }
+
+    pub fn apply_move (&mut self, from: Position, to: Position) -> Result<MoveMeta, MoveError> {
+        self.play(from, to, None)
+    }
+
+    pub fn apply_move_with_promotion (&mut self, from: Position, to: Position, promotion: Name) -> Result<MoveMeta, MoveError> {
+        self.play(from, to, Some(promotion))
+    }
+
+    pub fn apply (&mut self, mv: Move) -> Result<MoveMeta, MoveError> {
+        self.play(mv.from, mv.to, mv.promotion)
+    }
+
+    pub fn apply_san (&mut self, san: &str) -> Result<MoveMeta, SanError> {
+        let (from, to, promotion) = san::resolve(self, san)?;
+        self.play(from, to, promotion).map_err(SanError::Move)
+    }
+
+    pub fn apply_uci (&mut self, uci: &str) -> Result<MoveMeta, LanError> {
+        let (from, to, promotion) = lan::parse(uci)?;
+        self.play(from, to, promotion).map_err(LanError::Move)
+    }
+
+    pub fn perft (&self, depth: u32) -> u64 {
+        perft::perft(&mut self.board.clone(), self.turn, depth)
+    }
 
+    pub fn divide (&self, depth: u32) -> Vec<(String, u64)> {
+        perft::divide(&mut self.board.clone(), self.turn, depth)
+    }
+
+    // every legal move for the side to move; they are tried out on a copy of the board,
+    // so the game itself is only read
+    pub fn legal_moves (&self) -> Vec<Move> {
+        if !self.status.is_playable() {
+            return Vec::new();
+        }
+        rules::legal_moves(&mut self.board.clone(), self.turn)
+    }
+
+    pub fn legal_targets (&self, from: Position) -> Vec<Position> {
+        legal_targets(&mut self.board.clone(), from)
+    }
+
+    fn play (&mut self, from: Position, to: Position, promotion: Option<Name>) -> Result<MoveMeta, MoveError> {
+        let move_meta = self.make_move(from, to, promotion)?;
+        self.redo_stack.clear();
+        Ok(move_meta)
+    }
+
+    pub fn undo (&mut self) -> Option<MoveMeta> {
+        // every move played has one of each, they come off together
+        let (record, move_meta) = self.undo_stack.pop().zip(self.history.pop())?;
+        self.board.unmake_move(record.undo);
+
+        self.positions.pop();
+        self.turn = move_meta.piece_color;
+        self.halfmove_clock = record.halfmove_clock;
+        self.fullmove_number = record.fullmove_number;
+        self.status = record.status;
+        self.redo_stack.push(move_meta);
+
+        Some(move_meta)
+    }
+
+    pub fn redo (&mut self) -> Option<MoveMeta> {
+        let move_meta = self.redo_stack.pop()?;
+
+        match self.make_move(move_meta.from, move_meta.to, move_meta.promotion) {
+            Ok(move_meta) => Some(move_meta),
+            Err(_) => {
+                self.redo_stack.clear();
+                None
+            }
+        }
+    }
+
+    pub fn can_undo (&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo (&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn is_promotion (&self, from: Position, to: Position) -> bool {
+        self.board.get(from.row as i8, from.col as i8)
+            .is_some_and(|p| p.name == Name::Pawn && to.row == pawn::promotion_row(p.color))
+    }
+
+    fn make_move (&mut self, from: Position, to: Position, promotion: Option<Name>) -> Result<MoveMeta, MoveError> {
+        if self.status.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if self.status == GameStatus::Invalid {
+            return Err(MoveError::InvalidPosition);
+        }
+
+        let piece = self.board.get(from.row as i8, from.col as i8)
+            .ok_or(MoveError::NoPieceAtSource)?;
+
+        if self.turn != piece.color {
+            return Err(MoveError::NotYourTurn);
+        }
+
+        if !legal_targets(&mut self.board, from).contains(&to) {
+            return Err(MoveError::IllegalMove);
+        }
+
+        match (self.is_promotion(from, to), promotion) {
+            (true, Some(Name::Queen | Name::Rook | Name::Bishop | Name::Knight)) => {},
+            (true, None) => return Err(MoveError::PromotionRequired),
+            (_, Some(name)) => return Err(MoveError::InvalidPromotion(name)),
+            (false, None) => {},
+        }
+
+        let disambiguation = self.disambiguation(from, to);
+        let record = UndoRecord {
+            undo: self.board.make_move(from, to, promotion).ok_or(MoveError::NoPieceAtSource)?,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            status: self.status,
+        };
+
+        let mut move_meta = record.undo.meta();
+        move_meta.disambiguation = disambiguation;
+
+        self.tick_clocks(&move_meta);
         self.switch_turn();
+        self.positions.push(self.hash());
+        self.update_status();
+
+        move_meta.checkmate = matches!(self.status, GameStatus::Checkmate(_));
+        move_meta.check = move_meta.checkmate
+            || is_in_check(&self.board, self.turn) == Ok(true);
+        self.history.push(move_meta);
+        self.undo_stack.push(record);
+
+        Ok(move_meta)
+    }
+
+    fn disambiguation (&mut self, from: Position, to: Position) -> Disambiguation {
+        let (name, color) = match self.board.get(from.row as i8, from.col as i8) {
+            Some(p) if !matches!(p.name, Name::Pawn | Name::King) => (p.name, p.color),
+            _ => return Disambiguation::None,
+        };
+
+        let rivals: Vec<Position> = positions(self.board.pieces(name, color))
+            .filter(|&pos| pos != from)
+            .filter(|&pos| legal_targets(&mut self.board, pos).contains(&to))
+            .collect();
+
+        if rivals.is_empty() {
+            Disambiguation::None
+        } else if rivals.iter().all(|r| r.col != from.col) {
+            Disambiguation::File
+        } else if rivals.iter().all(|r| r.row != from.row) {
+            Disambiguation::Rank
+        } else {
+            Disambiguation::Square
+        }
     }
+
+    fn tick_clocks (&mut self, move_meta: &MoveMeta) -> () {
+        if move_meta.piece_name == Name::Pawn || move_meta.capture {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if move_meta.piece_color == Color::Black {
+            self.fullmove_number += 1;
+        }
+    }
+}
+
+// the squares the piece on from can go to, tried out on the board itself and taken back again
+fn legal_targets (board: &mut Board, from: Position) -> Vec<Position> {
+    let (color, mut moves) = match board.get(from.row as i8, from.col as i8) {
+        Some(piece) => (piece.color, piece.legal_moves(board)),
+        None => return Vec::new(),
+    };
+    filter_moves(board, &mut moves, from, color);
+
+    moves
 }
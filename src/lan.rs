@@ -0,0 +1,48 @@
+use std::fmt;
+
+use crate::game::MoveError;
+use crate::piece::{Name, Position};
+
+// long algebraic notation as spoken by UCI: "e2e4", "e7e8q", castling as "e1g1"
+
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub enum LanError {
+    Malformed(String),
+    Move(MoveError),
+}
+
+impl fmt::Display for LanError {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LanError::Malformed(s) => write!(f, "'{}' is not a long algebraic move", s),
+            LanError::Move(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for LanError {}
+
+pub fn parse (lan: &str) -> Result<(Position, Position, Option<Name>), LanError> {
+    let malformed = || LanError::Malformed(lan.to_string());
+
+    if !lan.is_ascii() || !(4..=5).contains(&lan.len()) {
+        return Err(malformed());
+    }
+
+    let from = Position::from_algebraic(&lan[0..2]).ok_or_else(malformed)?;
+    let to = Position::from_algebraic(&lan[2..4]).ok_or_else(malformed)?;
+    let promotion = match lan[4..].chars().next() {
+        Some(c) if c.is_ascii_lowercase() => Some(Name::from_letter(c.to_ascii_uppercase()).ok_or_else(malformed)?),
+        Some(_) => return Err(malformed()),
+        None => None,
+    };
+
+    Ok((from, to, promotion))
+}
+
+pub fn write (from: Position, to: Position, promotion: Option<Name>) -> String {
+    match promotion {
+        Some(name) => format!("{}{}{}", from, to, name.letter().to_ascii_lowercase()),
+        None => format!("{}{}", from, to),
+    }
+}
@@ -0,0 +1,14 @@
+#![allow(clippy::unused_unit)]
+
+pub mod game; pub mod board;
+pub mod piece; pub mod rules;
+pub mod fen; pub mod san;
+pub mod pgn; pub mod lan;
+pub mod perft; pub mod bitboard;
+pub mod zobrist; pub mod eval;
+pub mod search; pub mod transposition;
+pub mod uci; pub mod xboard;
+pub mod ordering; pub mod bench;
+
+mod utils;
+mod protocol;
@@ -1,5 +1,306 @@
-mod utils; mod game; mod board; mod piece; mod rules;
+#![allow(clippy::unused_unit)]
 
-fn main() {
+use iced::widget::{button, column, container, row, text, Column, Row};
+use iced::{Background, Border, Element, Length, Size, Task};
 
+use chess_rs::bitboard::{self, Bitboard};
+use chess_rs::game::{Game, GameStatus, MoveError};
+use chess_rs::piece::{Color, Move, MoveMeta, Name, Position};
+use chess_rs::eval::Evaluator;
+use chess_rs::search::{Limits, Search};
+use chess_rs::transposition::TranspositionTable;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+const SQUARE_SIZE: f32 = 72.0;
+// how long the engine thinks, the board is locked meanwhile
+const ENGINE_TIME: Duration = Duration::from_millis(500);
+
+const LIGHT_SQUARE: iced::Color = iced::Color::from_rgb(0.941, 0.851, 0.710);
+const DARK_SQUARE: iced::Color = iced::Color::from_rgb(0.710, 0.533, 0.388);
+const SELECTED_SQUARE: iced::Color = iced::Color::from_rgb(0.965, 0.965, 0.412);
+const TARGET_SQUARE: iced::Color = iced::Color::from_rgb(0.510, 0.592, 0.412);
+const ATTACKED_SQUARE: iced::Color = iced::Color::from_rgb(0.878, 0.478, 0.420);
+
+#[derive(Debug,Clone,Copy)]
+enum Message {
+    SquareClicked(Position),
+    Promote(Name),
+    Undo,
+    Redo,
+    ClaimDraw,
+    ToggleAttacks,
+    EngineMove,
+    EngineMoved(Option<Move>),
+}
+
+struct App {
+    game: Game,
+    selected: Option<Position>,
+    targets: Vec<Position>,
+    pending_promotion: Option<(Position, Position)>,
+    show_attacks: bool,
+    // a search is running off the UI thread, nothing may change the game until it is back
+    thinking: bool,
+    // kept from one engine move to the next, so each search starts from what the last one learnt
+    table: Arc<TranspositionTable>,
+    status: String,
+}
+
+impl Default for App {
+    fn default () -> App {
+        let mut app = App {
+            game: Game::new(),
+            selected: None,
+            targets: Vec::new(),
+            pending_promotion: None,
+            show_attacks: false,
+            thinking: false,
+            table: Arc::new(TranspositionTable::default()),
+            status: String::new(),
+        };
+        app.refresh_status();
+
+        app
+    }
+}
+
+impl App {
+    fn update (&mut self, message: Message) -> Task<Message> {
+        if self.thinking && !matches!(message, Message::EngineMoved(_) | Message::ToggleAttacks) {
+            return Task::none();
+        }
+
+        match message {
+            Message::SquareClicked(pos) => self.on_square_clicked(pos),
+            Message::Promote(name) => self.on_promote(name),
+            Message::Undo => {
+                self.game.undo();
+                self.reset_interaction();
+            }
+            Message::Redo => {
+                self.game.redo();
+                self.reset_interaction();
+            }
+            Message::ClaimDraw => {
+                let _ = self.game.claim_draw();
+                self.reset_interaction();
+            }
+            Message::ToggleAttacks => self.show_attacks = !self.show_attacks,
+            Message::EngineMove => return self.on_engine_move(),
+            Message::EngineMoved(mv) => self.on_engine_moved(mv),
+        }
+
+        Task::none()
+    }
+
+    fn on_square_clicked (&mut self, pos: Position) -> () {
+        self.pending_promotion = None;
+
+        if let Some(from) = self.selected {
+            if self.targets.contains(&pos) && self.game.is_promotion(from, pos) {
+                self.pending_promotion = Some((from, pos));
+                return;
+            }
+            if self.targets.contains(&pos) {
+                let result = self.game.apply_move(from, pos);
+                self.finish_move(result);
+                return;
+            }
+            if from == pos {
+                self.clear_selection();
+                return;
+            }
+        }
+
+        self.select(pos);
+    }
+
+    fn on_promote (&mut self, name: Name) -> () {
+        if let Some((from, to)) = self.pending_promotion.take() {
+            let result = self.game.apply_move_with_promotion(from, to, name);
+            self.finish_move(result);
+        }
+    }
+
+    // the search runs on the executor's threads and its move comes back as EngineMoved
+    fn on_engine_move (&mut self) -> Task<Message> {
+        self.pending_promotion = None;
+        self.clear_selection();
+        self.thinking = true;
+        self.status = String::from("Engine is thinking...");
+
+        let game = self.game.clone();
+        let limits = Limits { time: Some(ENGINE_TIME), ..Limits::default() };
+        let mut search = Search::with_table(limits, Evaluator::default(), Arc::clone(&self.table));
+        Task::perform(async move { search.run(&game).best_move }, Message::EngineMoved)
+    }
+
+    fn on_engine_moved (&mut self, mv: Option<Move>) -> () {
+        self.thinking = false;
+
+        match mv {
+            Some(mv) => {
+                let result = self.game.apply(mv);
+                self.finish_move(result);
+            }
+            None => self.refresh_status(),
+        }
+    }
+
+    fn finish_move (&mut self, result: Result<MoveMeta, MoveError>) -> () {
+        self.clear_selection();
+
+        match result {
+            Ok(_) => self.refresh_status(),
+            Err(err) => self.status = format!("Move rejected: {}", err),
+        }
+    }
+
+    fn select (&mut self, pos: Position) -> () {
+        self.clear_selection();
+
+        let own_piece = self.game.board.get(pos.row as i8, pos.col as i8)
+            .is_some_and(|p| p.color == self.game.get_turn());
+        if !own_piece { return; }
+
+        self.selected = Some(pos);
+        self.targets = self.game.legal_targets(pos);
+    }
+
+    fn reset_interaction (&mut self) -> () {
+        self.pending_promotion = None;
+        self.clear_selection();
+        self.refresh_status();
+    }
+
+    fn clear_selection (&mut self) -> () {
+        self.selected = None;
+        self.targets.clear();
+    }
+
+    fn refresh_status (&mut self) -> () {
+        let turn = self.game.get_turn();
+
+        self.status = match self.game.status() {
+            GameStatus::Ongoing => format!("{:?} to move", turn),
+            GameStatus::Check => format!("{:?} to move, in check", turn),
+            GameStatus::Checkmate(winner) => format!("Checkmate, {:?} wins", winner),
+            GameStatus::Stalemate => String::from("Stalemate"),
+            GameStatus::Draw(reason) => format!("Draw ({:?})", reason),
+            GameStatus::Resigned(winner) => format!("{:?} wins by resignation", winner),
+            GameStatus::TimeForfeit(winner) => format!("{:?} wins on time", winner),
+            GameStatus::Invalid => match self.game.validate() {
+                Err(problems) => format!("Invalid position: {}", problems[0]),
+                Ok(()) => String::from("Invalid position"),
+            },
+        };
+    }
+
+    fn view (&self) -> Element<'_, Message> {
+        // squares the side that just moved controls
+        let attacked = if self.show_attacks {
+            self.game.board.attack_map(self.game.get_turn().opposite())
+        } else {
+            0
+        };
+
+        let board = (0..8).fold(Column::new(), |board, row| {
+            board.push((0..8).fold(Row::new(), |rank, col| {
+                rank.push(self.square(Position { row, col }, attacked))
+            }))
+        });
+
+        let status = text(&self.status).size(20).width(Length::Fill);
+        let idle = !self.thinking;
+        let controls = row![
+            button("Undo").on_press_maybe((idle && self.game.can_undo()).then_some(Message::Undo)),
+            button("Redo").on_press_maybe((idle && self.game.can_redo()).then_some(Message::Redo)),
+            button("Claim draw").on_press_maybe(self.game.claimable_draw().filter(|_| idle).map(|_| Message::ClaimDraw)),
+            button("Engine move").on_press_maybe((idle && self.game.status().is_playable()).then_some(Message::EngineMove)),
+            button(if self.show_attacks { "Hide attacks" } else { "Show attacks" }).on_press(Message::ToggleAttacks),
+        ].spacing(8);
+
+        let mut content = column![board, status, controls].spacing(12);
+        if self.pending_promotion.is_some() {
+            content = content.push(self.promotion_picker());
+        }
+
+        container(content)
+            .padding(16)
+            .into()
+    }
+
+    fn promotion_picker (&self) -> Element<'_, Message> {
+        let color = self.game.get_turn();
+
+        [Name::Queen, Name::Rook, Name::Bishop, Name::Knight].into_iter()
+            .fold(row![text("Promote to").size(20)].spacing(8), |picker, name| {
+                let label = glyph(color, name);
+                picker.push(button(text(label).size(28)).on_press(Message::Promote(name)))
+            })
+            .into()
+    }
+
+    fn square (&self, pos: Position, attacked: Bitboard) -> Element<'_, Message> {
+        let background = if self.selected == Some(pos) {
+            SELECTED_SQUARE
+        } else if self.targets.contains(&pos) {
+            TARGET_SQUARE
+        } else if attacked & bitboard::bit(pos) != 0 {
+            ATTACKED_SQUARE
+        } else if (pos.row + pos.col).is_multiple_of(2) {
+            LIGHT_SQUARE
+        } else {
+            DARK_SQUARE
+        };
+
+        let label = self.game.board
+            .get(pos.row as i8, pos.col as i8)
+            .map_or("", |p| glyph(p.color, p.name));
+
+        button(
+            text(label)
+                .size(SQUARE_SIZE * 0.7)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center()
+        )
+        .width(SQUARE_SIZE)
+        .height(SQUARE_SIZE)
+        .padding(0)
+        .style(move |_theme, _status| button::Style {
+            background: Some(Background::Color(background)),
+            text_color: iced::Color::BLACK,
+            border: Border::default(),
+            ..button::Style::default()
+        })
+        .on_press(Message::SquareClicked(pos))
+        .into()
+    }
+}
+
+fn glyph (color: Color, name: Name) -> &'static str {
+    match (color, name) {
+        (Color::White, Name::King) => "\u{2654}",
+        (Color::White, Name::Queen) => "\u{2655}",
+        (Color::White, Name::Rook) => "\u{2656}",
+        (Color::White, Name::Bishop) => "\u{2657}",
+        (Color::White, Name::Knight) => "\u{2658}",
+        (Color::White, Name::Pawn) => "\u{2659}",
+        (Color::Black, Name::King) => "\u{265A}",
+        (Color::Black, Name::Queen) => "\u{265B}",
+        (Color::Black, Name::Rook) => "\u{265C}",
+        (Color::Black, Name::Bishop) => "\u{265D}",
+        (Color::Black, Name::Knight) => "\u{265E}",
+        (Color::Black, Name::Pawn) => "\u{265F}",
+    }
+}
+
+fn main() -> iced::Result {
+    iced::application("chess_rs", App::update, App::view)
+        .window_size(Size::new(SQUARE_SIZE * 8.0 + 32.0, SQUARE_SIZE * 8.0 + 170.0))
+        .resizable(false)
+        .run()
 }
@@ -0,0 +1,268 @@
+use crate::bitboard::{bit, positions, square};
+use crate::board::Board;
+use crate::eval::value;
+use crate::piece::{Color, Move, MoveKind, Name, Position};
+use crate::search::MAX_PLY;
+
+// history scores are halved once any of them climbs past this
+const HISTORY_MAX: i32 = 1 << 20;
+// the king can only be the last piece into an exchange, anything after it would be illegal
+const SEE_KING: i32 = 20_000;
+
+pub fn is_quiet (mv: &Move) -> bool {
+    !mv.is_capture() && mv.promotion.is_none()
+}
+
+// most valuable victim first, and of those the least valuable attacker
+pub fn mvv_lva (board: &Board, mv: &Move) -> i32 {
+    let attacker = board.get(mv.from.row as i8, mv.from.col as i8)
+        .map_or(Name::Pawn, |p| p.name);
+    let rank = attacker.index() as i32;
+
+    (victim_value(board, mv) + promotion_gain(mv)) * 8 - rank
+}
+
+// static exchange evaluation: what the side making the capture ends up with when both sides
+// keep recapturing on the target square with their cheapest piece, either one free to stop
+pub fn see (board: &Board, mv: &Move) -> i32 {
+    let Some(mover) = board.get(mv.from.row as i8, mv.from.col as i8) else { return 0 };
+
+    let mut occupied = board.occupied() ^ bit(mv.from);
+    if mv.kind == MoveKind::EnPassant {
+        occupied ^= bit(Position { row: mv.from.row, col: mv.to.col });
+    }
+
+    let mut gain = [0; 32];
+    let mut depth = 0;
+    gain[0] = victim_value(board, mv) + promotion_gain(mv);
+    // what stands on the target square and is next to be taken
+    let mut standing = match mv.promotion {
+        Some(name) => value(name),
+        None => see_value(mover.name),
+    };
+    let mut side = mover.color.opposite();
+
+    loop {
+        let attackers = board.attackers_through(mv.to, side, occupied) & occupied;
+        let Some((name, from)) = cheapest(board, attackers, side) else { break };
+
+        // neither side can come out ahead by taking, the exchange is settled
+        let next = standing - gain[depth];
+        if next.max(-gain[depth]) < 0 || depth == gain.len() - 1 {
+            break;
+        }
+        depth += 1;
+        gain[depth] = next;
+
+        standing = see_value(name);
+        occupied ^= bit(from);
+        side = side.opposite();
+    }
+
+    while depth > 0 {
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        depth -= 1;
+    }
+
+    gain[0]
+}
+
+// Name::ALL runs cheapest first, the order attackers join an exchange in
+fn cheapest (board: &Board, attackers: u64, side: Color) -> Option<(Name, Position)> {
+    Name::ALL.iter().find_map(|&name| {
+        positions(attackers & board.pieces(name, side)).next().map(|pos| (name, pos))
+    })
+}
+
+fn see_value (name: Name) -> i32 {
+    if name == Name::King { SEE_KING } else { value(name) }
+}
+
+fn victim_value (board: &Board, mv: &Move) -> i32 {
+    match mv.kind {
+        MoveKind::EnPassant => value(Name::Pawn),
+        _ => board.get(mv.to.row as i8, mv.to.col as i8).map_or(0, |p| value(p.name)),
+    }
+}
+
+fn promotion_gain (mv: &Move) -> i32 {
+    mv.promotion.map_or(0, |name| value(name) - value(Name::Pawn))
+}
+
+// two quiet moves per ply that recently caused a cutoff, siblings often refute the same way
+pub struct Killers {
+    moves: [[Option<Move>; 2]; MAX_PLY],
+}
+
+impl Default for Killers {
+    fn default () -> Killers {
+        Killers { moves: [[None; 2]; MAX_PLY] }
+    }
+}
+
+impl Killers {
+    pub fn get (&self, ply: usize) -> [Option<Move>; 2] {
+        self.moves.get(ply).copied().unwrap_or_default()
+    }
+
+    pub fn add (&mut self, ply: usize, mv: Move) -> () {
+        let Some(slots) = self.moves.get_mut(ply) else { return };
+        if slots[0] != Some(mv) {
+            slots[1] = slots[0];
+            slots[0] = Some(mv);
+        }
+    }
+
+    pub fn clear (&mut self) -> () {
+        self.moves = [[None; 2]; MAX_PLY];
+    }
+}
+
+// how often a quiet move caused a cutoff anywhere in the tree, weighted by depth
+pub struct History {
+    scores: Box<[[[i32; 64]; 64]; 2]>,
+}
+
+impl Default for History {
+    fn default () -> History {
+        History { scores: Box::new([[[0; 64]; 64]; 2]) }
+    }
+}
+
+impl History {
+    pub fn get (&self, color: Color, mv: &Move) -> i32 {
+        self.scores[color.index()][square(mv.from)][square(mv.to)]
+    }
+
+    pub fn add (&mut self, color: Color, mv: &Move, depth: u32) -> () {
+        let score = &mut self.scores[color.index()][square(mv.from)][square(mv.to)];
+        *score += (depth * depth) as i32;
+
+        if *score > HISTORY_MAX {
+            self.age();
+        }
+    }
+
+    // older searches count for less but are not forgotten
+    pub fn age (&mut self) -> () {
+        for score in self.scores.iter_mut().flatten().flatten() {
+            *score /= 2;
+        }
+    }
+}
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+enum Stage {
+    Hash,
+    GoodCaptures,
+    Killers,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+// hands out the moves of a node best first, one stage at a time: the hash move, captures
+// that do not lose material, killers, the other quiet moves by history, losing captures;
+// nothing is scored until its stage comes up, a cutoff on the hash move costs nothing
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    color: Color,
+    // everything not yet handed out or sorted into a stage
+    moves: Vec<Move>,
+    scored: Vec<(Move, i32)>,
+    quiets: Vec<Move>,
+    bad_captures: Vec<(Move, i32)>,
+}
+
+impl MovePicker {
+    pub fn new (moves: Vec<Move>, hash_move: Option<Move>, killers: [Option<Move>; 2], color: Color) -> MovePicker {
+        MovePicker {
+            stage: Stage::Hash,
+            hash_move,
+            killers,
+            color,
+            moves,
+            scored: Vec::new(),
+            quiets: Vec::new(),
+            bad_captures: Vec::new(),
+        }
+    }
+
+    // the board must be the one the moves were generated for
+    pub fn next (&mut self, board: &Board, history: &History) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::Hash => {
+                    self.stage = Stage::GoodCaptures;
+                    if let Some(hash_move) = self.hash_move
+                        && let Some(i) = self.moves.iter().position(|&mv| mv == hash_move) {
+                        return Some(self.moves.swap_remove(i));
+                    }
+                }
+                Stage::GoodCaptures => {
+                    if !self.moves.is_empty() {
+                        self.split(board);
+                    }
+                    if let Some(mv) = pick(&mut self.scored) {
+                        return Some(mv);
+                    }
+                    self.stage = Stage::Killers;
+                }
+                Stage::Killers => {
+                    let killer = self.killers.iter_mut()
+                        .filter_map(|slot| slot.take())
+                        .find_map(|killer| {
+                            let i = self.quiets.iter().position(|&mv| mv == killer)?;
+                            Some(self.quiets.swap_remove(i))
+                        });
+                    if killer.is_some() {
+                        return killer;
+                    }
+
+                    self.scored = self.quiets.drain(..)
+                        .map(|mv| (mv, history.get(self.color, &mv)))
+                        .collect();
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    if let Some(mv) = pick(&mut self.scored) {
+                        return Some(mv);
+                    }
+                    self.stage = Stage::BadCaptures;
+                }
+                Stage::BadCaptures => {
+                    if let Some(mv) = pick(&mut self.bad_captures) {
+                        return Some(mv);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    // with the hash move out of the way, everything left goes into its stage
+    fn split (&mut self, board: &Board) -> () {
+        for mv in self.moves.drain(..) {
+            if is_quiet(&mv) {
+                self.quiets.push(mv);
+            } else if see(board, &mv) >= 0 {
+                self.scored.push((mv, mvv_lva(board, &mv)));
+            } else {
+                self.bad_captures.push((mv, mvv_lva(board, &mv)));
+            }
+        }
+    }
+}
+
+// takes out the best scored move, ties go to whichever was generated first
+fn pick (moves: &mut Vec<(Move, i32)>) -> Option<Move> {
+    let best = moves.iter()
+        .enumerate()
+        .max_by_key(|&(i, &(_, score))| (score, std::cmp::Reverse(i)))
+        .map(|(i, _)| i)?;
+
+    Some(moves.remove(best).0)
+}
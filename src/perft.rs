@@ -0,0 +1,40 @@
+use crate::board::Board;
+use crate::piece::Color;
+use crate::rules::legal_moves;
+
+// counts the leaf nodes of the legal move tree, depth 0 being the position itself
+pub fn perft (board: &mut Board, color: Color, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = legal_moves(board, color);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves.into_iter()
+        .map(|mv| {
+            let undo = board.make_move(mv.from, mv.to, mv.promotion).expect("legal move from an empty square");
+            let nodes = perft(board, color.opposite(), depth - 1);
+            board.unmake_move(undo);
+            nodes
+        })
+        .sum()
+}
+
+// perft split by root move, keyed by the move in UCI notation
+pub fn divide (board: &mut Board, color: Color, depth: u32) -> Vec<(String, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    legal_moves(board, color).into_iter()
+        .map(|mv| {
+            let undo = board.make_move(mv.from, mv.to, mv.promotion).expect("legal move from an empty square");
+            let nodes = perft(board, color.opposite(), depth - 1);
+            board.unmake_move(undo);
+            (mv.to_string(), nodes)
+        })
+        .collect()
+}
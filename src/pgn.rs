@@ -0,0 +1,505 @@
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::fen::{FenError, STARTING_FEN};
+use crate::game::{Game, GameStatus};
+use crate::piece::Color;
+use crate::san::SanError;
+
+static SEVEN_TAG_ROSTER: &[(&str, &str)] = &[
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+static RESULTS: &[&str] = &["1-0", "0-1", "1/2-1/2", "*"];
+
+const LINE_WIDTH: usize = 80;
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    Syntax(String),
+    Fen(FenError),
+    Move { ply: usize, san: String, error: SanError },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Io(err) => write!(f, "read failed: {}", err),
+            PgnError::Syntax(msg) => write!(f, "syntax error: {}", msg),
+            PgnError::Fen(err) => write!(f, "bad FEN tag: {}", err),
+            PgnError::Move { ply, san, error } => write!(f, "move '{}' at ply {}: {}", san, ply + 1, error),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<io::Error> for PgnError {
+    fn from (err: io::Error) -> PgnError {
+        PgnError::Io(err)
+    }
+}
+
+#[derive(Debug,PartialEq,Eq,Clone,Default)]
+pub struct PgnMove {
+    pub san: String,
+    pub nags: Vec<u8>,
+    pub comments: Vec<String>,
+    pub variations: Vec<Variation>,
+}
+
+// an alternative to the move it hangs off, with any comment that precedes its first move
+#[derive(Debug,PartialEq,Eq,Clone,Default)]
+pub struct Variation {
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+}
+
+#[derive(Debug,PartialEq,Eq,Clone,Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag (&self, name: &str) -> Option<&str> {
+        self.tags.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn parse (pgn: &str) -> Result<PgnGame, PgnError> {
+        PgnReader::new(pgn.as_bytes())
+            .next()
+            .unwrap_or_else(|| Err(PgnError::Syntax(String::from("no game found"))))
+    }
+
+    // replays the main line through the regular move path; the game ends up as the moves
+    // leave it, a result decided off the board (resignation, agreement) only lives in
+    // the result and the Result tag
+    pub fn to_game (&self) -> Result<Game, PgnError> {
+        let mut game = match self.tag("FEN") {
+            Some(fen) => Game::from_fen(fen).map_err(PgnError::Fen)?,
+            None => Game::new(),
+        };
+
+        for (ply, mv) in self.moves.iter().enumerate() {
+            game.apply_san(&mv.san).map_err(|error| PgnError::Move {
+                ply,
+                san: mv.san.clone(),
+                error,
+            })?;
+        }
+
+        Ok(game)
+    }
+
+    pub fn from_game (game: &Game, tags: &[(&str, &str)]) -> PgnGame {
+        let result = result_token(game.status()).to_string();
+
+        let mut all_tags: Vec<(String, String)> = SEVEN_TAG_ROSTER.iter()
+            .map(|&(name, default)| {
+                let value = match name {
+                    "Result" => result.as_str(),
+                    _ => tags.iter().find(|(key, _)| *key == name).map_or(default, |&(_, value)| value),
+                };
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+
+        // a game built by hand has no starting position to point back to
+        if !game.start_fen().is_empty() && game.start_fen() != STARTING_FEN {
+            all_tags.push((String::from("SetUp"), String::from("1")));
+            all_tags.push((String::from("FEN"), game.start_fen().to_string()));
+        }
+
+        for &(key, value) in tags {
+            if !all_tags.iter().any(|(existing, _)| existing == key) {
+                all_tags.push((key.to_string(), value.to_string()));
+            }
+        }
+
+        PgnGame {
+            tags: all_tags,
+            comments: Vec::new(),
+            moves: game.history().iter()
+                .map(|mv| PgnMove { san: mv.to_string(), ..PgnMove::default() })
+                .collect(),
+            result,
+        }
+    }
+
+    fn first_ply (&self) -> usize {
+        // plies are counted from the start of the game, so black moves are odd
+        self.tag("FEN")
+            .and_then(|fen| {
+                let fields: Vec<&str> = fen.split_whitespace().collect();
+                let fullmove = fields.get(5)?.parse::<usize>().ok()?;
+                let black = *fields.get(1)? == "b";
+                Some(fullmove.saturating_sub(1) * 2 + black as usize)
+            })
+            .unwrap_or(0)
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        let mut tokens: Vec<String> = self.comments.iter().map(|c| format!("{{{}}}", c)).collect();
+        write_moves(&mut tokens, &self.moves, self.first_ply());
+        tokens.push(if self.result.is_empty() { String::from("*") } else { self.result.clone() });
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+fn write_moves (tokens: &mut Vec<String>, moves: &[PgnMove], first_ply: usize) -> () {
+    // black moves need their number spelled out after anything that interrupts the line
+    let mut needs_number = true;
+
+    for (i, mv) in moves.iter().enumerate() {
+        let ply = first_ply + i;
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}. {}", ply / 2 + 1, mv.san));
+        } else if needs_number {
+            tokens.push(format!("{}... {}", ply / 2 + 1, mv.san));
+        } else {
+            tokens.push(mv.san.clone());
+        }
+
+        tokens.extend(mv.nags.iter().map(|nag| format!("${}", nag)));
+        tokens.extend(mv.comments.iter().map(|c| format!("{{{}}}", c)));
+
+        for variation in &mv.variations {
+            let mut inner: Vec<String> = variation.comments.iter().map(|c| format!("{{{}}}", c)).collect();
+            write_moves(&mut inner, &variation.moves, ply);
+            tokens.push(format!("({})", inner.join(" ")));
+        }
+
+        needs_number = !mv.comments.is_empty() || !mv.variations.is_empty();
+    }
+}
+
+pub fn result_token (status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::Checkmate(winner) | GameStatus::Resigned(winner) | GameStatus::TimeForfeit(winner) => {
+            match winner {
+                Color::White => "1-0",
+                Color::Black => "0-1",
+            }
+        }
+        GameStatus::Stalemate | GameStatus::Draw(_) => "1/2-1/2",
+        GameStatus::Ongoing | GameStatus::Check | GameStatus::Invalid => "*",
+    }
+}
+
+#[derive(Debug,PartialEq,Eq,Clone)]
+enum Token {
+    TagOpen,
+    TagClose,
+    Str(String),
+    Symbol(String),
+    MoveNumber,
+    Nag(u8),
+    Comment(String),
+    VariationOpen,
+    VariationClose,
+    Result(String),
+}
+
+// reads games one at a time, so multi-game files never have to sit in memory whole
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    line: Vec<char>,
+    cursor: usize,
+    peeked: Option<Token>,
+    // a read failed, nothing more will come
+    done: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new (reader: R) -> PgnReader<R> {
+        PgnReader {
+            reader,
+            line: Vec::new(),
+            cursor: 0,
+            peeked: None,
+            done: false,
+        }
+    }
+
+    fn peek_char (&mut self) -> Result<Option<char>, PgnError> {
+        while self.cursor >= self.line.len() {
+            let mut buf = String::new();
+            if self.reader.read_line(&mut buf)? == 0 {
+                return Ok(None);
+            }
+            // lines starting with '%' are escaped from PGN processing
+            if buf.starts_with('%') {
+                continue;
+            }
+            self.line = buf.chars().collect();
+            self.cursor = 0;
+        }
+
+        Ok(Some(self.line[self.cursor]))
+    }
+
+    fn next_char (&mut self) -> Result<Option<char>, PgnError> {
+        let c = self.peek_char()?;
+        if c.is_some() {
+            self.cursor += 1;
+        }
+        Ok(c)
+    }
+
+    fn peek_token (&mut self) -> Result<Option<Token>, PgnError> {
+        if self.peeked.is_none() {
+            self.peeked = self.lex()?;
+        }
+        Ok(self.peeked.clone())
+    }
+
+    fn next_token (&mut self) -> Result<Option<Token>, PgnError> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.lex(),
+        }
+    }
+
+    fn lex (&mut self) -> Result<Option<Token>, PgnError> {
+        while self.peek_char()?.is_some_and(char::is_whitespace) {
+            self.next_char()?;
+        }
+
+        let c = match self.next_char()? {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        let token = match c {
+            '[' => Token::TagOpen,
+            ']' => Token::TagClose,
+            '(' => Token::VariationOpen,
+            ')' => Token::VariationClose,
+            '"' => Token::Str(self.lex_string()?),
+            '{' => Token::Comment(self.lex_until('}', true)?),
+            ';' => Token::Comment(self.lex_until('\n', false)?),
+            '$' => {
+                let digits = self.lex_while(|c| c.is_ascii_digit())?;
+                Token::Nag(digits.parse().map_err(|_| PgnError::Syntax(format!("bad NAG '${}'", digits)))?)
+            }
+            '!' | '?' => {
+                let glyph = format!("{}{}", c, self.lex_while(|c| c == '!' || c == '?')?);
+                Token::Nag(match glyph.as_str() {
+                    "!" => 1,
+                    "?" => 2,
+                    "!!" => 3,
+                    "??" => 4,
+                    "!?" => 5,
+                    "?!" => 6,
+                    _ => return Err(PgnError::Syntax(format!("bad annotation '{}'", glyph))),
+                })
+            }
+            c if c.is_ascii_alphanumeric() => {
+                let symbol = format!("{}{}", c, self.lex_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(c))?);
+                if RESULTS.contains(&symbol.as_str()) {
+                    Token::Result(symbol)
+                } else if symbol.chars().all(|c| c.is_ascii_digit()) {
+                    self.lex_while(|c| c == '.')?;
+                    Token::MoveNumber
+                } else {
+                    Token::Symbol(symbol)
+                }
+            }
+            '*' => Token::Result(String::from("*")),
+            '.' => Token::MoveNumber,
+            other => return Err(PgnError::Syntax(format!("unexpected character '{}'", other))),
+        };
+
+        Ok(Some(token))
+    }
+
+    fn lex_while (&mut self, accept: impl Fn(char) -> bool) -> Result<String, PgnError> {
+        let mut out = String::new();
+        while let Some(c) = self.peek_char()? {
+            if !accept(c) { break; }
+            out.push(c);
+            self.next_char()?;
+        }
+        Ok(out)
+    }
+
+    fn lex_until (&mut self, end: char, required: bool) -> Result<String, PgnError> {
+        let mut out = String::new();
+        loop {
+            match self.next_char()? {
+                Some(c) if c == end => break,
+                Some(c) => out.push(c),
+                None if required => return Err(PgnError::Syntax(String::from("unterminated comment"))),
+                None => break,
+            }
+        }
+        Ok(out.trim().to_string())
+    }
+
+    fn lex_string (&mut self) -> Result<String, PgnError> {
+        let mut out = String::new();
+        loop {
+            match self.next_char()? {
+                Some('"') => return Ok(out),
+                Some('\\') => {
+                    if let Some(c) = self.next_char()? {
+                        out.push(c);
+                    }
+                }
+                Some(c) => out.push(c),
+                None => return Err(PgnError::Syntax(String::from("unterminated tag value"))),
+            }
+        }
+    }
+
+    // after a broken game, drops everything up to where the next one looks to begin:
+    // an Event tag, or any tag line that follows a blank line
+    fn skip_game (&mut self) -> Result<(), PgnError> {
+        self.peeked = None;
+        self.line.clear();
+        self.cursor = 0;
+
+        let mut blank = false;
+        loop {
+            let mut buf = String::new();
+            if self.reader.read_line(&mut buf)? == 0 {
+                return Ok(());
+            }
+            let line = buf.trim_start();
+            if line.starts_with("[Event ") || (blank && line.starts_with('[')) {
+                self.line = buf.chars().collect();
+                return Ok(());
+            }
+            blank = line.is_empty();
+        }
+    }
+
+    fn parse_game (&mut self) -> Result<Option<PgnGame>, PgnError> {
+        if self.peek_token()?.is_none() {
+            return Ok(None);
+        }
+
+        let mut game = PgnGame::default();
+
+        while self.peek_token()? == Some(Token::TagOpen) {
+            self.next_token()?;
+            let name = match self.next_token()? {
+                Some(Token::Symbol(name)) => name,
+                other => return Err(PgnError::Syntax(format!("expected tag name, found {:?}", other))),
+            };
+            let value = match self.next_token()? {
+                Some(Token::Str(value)) => value,
+                other => return Err(PgnError::Syntax(format!("expected tag value, found {:?}", other))),
+            };
+            if self.next_token()? != Some(Token::TagClose) {
+                return Err(PgnError::Syntax(format!("unterminated tag '{}'", name)));
+            }
+            game.tags.push((name, value));
+        }
+
+        let mainline = self.parse_variation(false)?;
+        game.comments = mainline.comments;
+        game.moves = mainline.moves;
+
+        game.result = match self.peek_token()? {
+            Some(Token::Result(result)) => {
+                self.next_token()?;
+                result
+            }
+            _ => String::from("*"),
+        };
+
+        Ok(Some(game))
+    }
+
+    fn parse_variation (&mut self, nested: bool) -> Result<Variation, PgnError> {
+        let mut variation = Variation::default();
+
+        loop {
+            match self.peek_token()? {
+                // a game missing its result ends where the next one's tags begin
+                Some(Token::Result(_) | Token::TagOpen) | None if !nested => break,
+                Some(Token::VariationClose) if nested => break,
+                None => return Err(PgnError::Syntax(String::from("unterminated variation"))),
+                _ => {},
+            }
+
+            match self.next_token()? {
+                Some(Token::MoveNumber) => {},
+                Some(Token::Symbol(san)) => variation.moves.push(PgnMove { san, ..PgnMove::default() }),
+                Some(Token::Nag(nag)) => match variation.moves.last_mut() {
+                    Some(mv) => mv.nags.push(nag),
+                    None => return Err(PgnError::Syntax(String::from("annotation before any move"))),
+                },
+                Some(Token::Comment(comment)) => match variation.moves.last_mut() {
+                    Some(mv) => mv.comments.push(comment),
+                    None => variation.comments.push(comment),
+                },
+                Some(Token::VariationOpen) => {
+                    let inner = self.parse_variation(true)?;
+                    self.next_token()?;
+                    match variation.moves.last_mut() {
+                        Some(mv) => mv.variations.push(inner),
+                        None => return Err(PgnError::Syntax(String::from("variation before any move"))),
+                    }
+                }
+                // a result inside a variation is allowed and simply ends it
+                Some(Token::Result(_)) => {},
+                other => return Err(PgnError::Syntax(format!("unexpected {:?}", other))),
+            }
+        }
+
+        Ok(variation)
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    // an error is reported for the broken game and reading goes on with the next one
+    fn next (&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.parse_game() {
+            Ok(game) => game.map(Ok),
+            Err(err) => {
+                self.done = matches!(err, PgnError::Io(_)) || self.skip_game().is_err();
+                Some(Err(err))
+            }
+        }
+    }
+}
@@ -1,10 +1,10 @@
-use crate::utils::sliding_moves;
+use crate::bitboard::{bishop_attacks, positions};
 use crate::piece::{Color, MoveMeta, Name, Piece, PieceData, Position};
 use crate::board::Board;
 
 use std::any::Any;
 
-static DIRS: &[(i8, i8)] = &[
+pub static DIRS: &[(i8, i8)] = &[
     (1, 1),
     (-1, -1),
     (-1, 1),
@@ -20,7 +20,7 @@ impl PieceData for BishopData {
     fn as_any_mut (&mut self) -> &mut dyn Any { self }
 
     fn legal_moves (&self, pos: Position, color: Color, board: &Board) -> Vec<Position> {
-        sliding_moves(board, pos, DIRS, Some(color))
+        positions(bishop_attacks(pos, board.occupied()) & !board.occupancy(color)).collect()
     }
 
     fn on_move (&mut self, _from: Position, _to: Position, _color: Color, _board: &mut Board) -> Option<MoveMeta> {None}
@@ -1,12 +1,12 @@
 use crate::piece::rook::RookData;
-use crate::rules::{filter_moves, is_in_check};
-use crate::utils::{step_moves, sliding_moves};
-use crate::piece::{Color, MoveMeta, Name, Piece, PieceData, Position};
+use crate::bitboard::{king_attacks, positions};
+use crate::utils::sliding_moves;
+use crate::piece::{Color, Disambiguation, MoveMeta, Name, Piece, PieceData, Position};
 use crate::board::Board;
 
 use std::any::Any;
 
-static STEPS: &[(i8, i8)] = &[
+pub static STEPS: &[(i8, i8)] = &[
     (1,0), (-1,0),
     (1,-1), (0,-1), (-1,-1),
     (-1,1), (0,1), (1,1),
@@ -25,38 +25,56 @@ pub struct KingData {
 impl PieceData for KingData {
     fn as_any (&self) -> &dyn Any { self }
     fn as_any_mut (&mut self) -> &mut dyn Any { self }
+    fn has_moved (&self) -> bool { self.has_moved }
+    fn set_moved (&mut self, moved: bool) -> () { self.has_moved = moved; }
 
     fn legal_moves (&self, pos: Position, color: Color, board: &Board) -> Vec<Position> {
-        let mut moves = step_moves(board, pos, STEPS, color);
-        if self.has_moved { return moves; }
+        let mut moves: Vec<Position> = positions(king_attacks(pos) & !board.occupancy(color)).collect();
+        if self.has_moved || board.is_attacked(pos, color.opposite()) { return moves; }
 
-        let sliding_moves = sliding_moves(board, pos, CASTLE_DIRS, None);
-        for mv in sliding_moves.iter() {
-            let mut b_clone = board.clone();
-            if let Some(king_p) = b_clone.take(pos.row as i8, pos.col as i8) {
-                b_clone.set(mv.row as i8, mv.col as i8, Some(king_p));
-                if is_in_check(&b_clone, color) { break; }
-            }
+        for &dir in CASTLE_DIRS {
+            // the first occupied square along the rank has to be our unmoved rook
+            let rook_pos = match sliding_moves(board, pos, &[dir], None).last() {
+                Some(&p) => p,
+                None => continue,
+            };
+            let rook_ready = board.get(rook_pos.row as i8, rook_pos.col as i8)
+                .filter(|p| p.name == Name::Rook && p.color == color)
+                .and_then(|p| p.data.as_any().downcast_ref::<RookData>())
+                .is_some_and(|data| !data.has_moved);
+            if !rook_ready { continue; }
 
-            if let Some(p) = board.get(mv.row as i8, mv.col as i8) {
-                if p.name != Name::Rook || p.color != color { continue; }
-                if let Some(data) = p.data.as_any().downcast_ref::<RookData>() {
-                    if data.has_moved { continue; }
-                    let d_col = (mv.col as i8 - pos.col as i8).signum();
-                    if let Some(castle_target) = pos.shifted(d_col*2, 0) {
-                        moves.push(castle_target);
-                    }
-                }
+            // neither the square the king crosses nor the one it lands on may be attacked
+            let path = [pos.shifted(dir.0, 0), pos.shifted(dir.0 * 2, 0)];
+            let safe = path.iter().all(|step| match step {
+                Some(step) => *step != rook_pos && !board.is_attacked(*step, color.opposite()),
+                None => false,
+            });
+
+            if safe && let Some(castle_target) = path[1] {
+                moves.push(castle_target);
             }
         }
-        
+
         moves
     }
 
     fn on_move (&mut self, from: Position, to: Position, color: Color, board: &mut Board) -> Option<MoveMeta> {
         let delta_col = to.col as i8 - from.col as i8;
-        let is_castle = delta_col.abs() == 2;
+        let castle = !self.has_moved && delta_col.abs() == 2;
         self.moved();
+
+        if castle {
+            let rook_col = if delta_col > 0 { 7 } else { 0 };
+            if let Some(mut rook) = board.take(from.row as i8, rook_col) {
+                if let Some(data) = rook.data.as_any_mut().downcast_mut::<RookData>() {
+                    data.moved();
+                }
+                rook.pos = Position { row: from.row, col: (from.col as i8 + delta_col / 2) as usize };
+                board.set(rook.pos.row as i8, rook.pos.col as i8, Some(rook));
+            }
+        }
+
         Some(MoveMeta {
             piece_name: Name::King,
             piece_color: color,
@@ -64,7 +82,10 @@ impl PieceData for KingData {
             to,
             capture: board.get(to.row as i8, to.col as i8).is_some(),
             promotion: None,
-            castle: !self.has_moved && is_castle
+            castle,
+            check: false,
+            checkmate: false,
+            disambiguation: Disambiguation::None,
         })
     }
 }
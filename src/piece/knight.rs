@@ -1,10 +1,10 @@
-use crate::utils::step_moves;
+use crate::bitboard::{knight_attacks, positions};
 use crate::piece::{Color, MoveMeta, Name, Piece, PieceData, Position};
 use crate::board::Board;
 
 use std::any::Any;
 
-static STEPS: &[(i8, i8)] = &[
+pub static STEPS: &[(i8, i8)] = &[
     ( 2,  1), ( 2, -1),
     (-2,  1), (-2, -1),
     ( 1,  2), ( 1, -2),
@@ -19,7 +19,7 @@ impl PieceData for KnightData {
     fn as_any_mut (&mut self) -> &mut dyn Any { self }
 
     fn legal_moves (&self, pos: Position, color: Color, board: &Board) -> Vec<Position> {
-        step_moves(board, pos, STEPS, color)
+        positions(knight_attacks(pos) & !board.occupancy(color)).collect()
     }
 
     fn on_move (&mut self, _from: Position, _to: Position, _color: Color, _board: &mut Board) -> Option<MoveMeta> {None}
@@ -2,15 +2,16 @@ pub mod pawn; pub mod rook;
 pub mod king; pub mod queen;
 pub mod knight; pub mod bishop;
 
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::any::Any;
 
 use crate::board::Board;
+use crate::lan;
 use crate::utils::validate_pos;
 
 #[derive(Debug,PartialEq,Eq,Clone,Copy)]
 pub enum Name {
-    Pawn, King, Queen, Rook, Knight, Bishop
+    Pawn, Knight, Bishop, Rook, Queen, King
 }
 
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Default)]
@@ -26,6 +27,16 @@ pub struct Position {
     pub col: usize
 }
 
+// how much of the source square SAN has to spell out to tell the mover apart
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Default)]
+pub enum Disambiguation {
+    #[default]
+    None,
+    File,
+    Rank,
+    Square,
+}
+
 #[derive(Debug,Clone,Copy)]
 pub struct MoveMeta {
     pub piece_name: Name,
@@ -35,6 +46,27 @@ pub struct MoveMeta {
     pub capture: bool,
     pub promotion: Option<Name>,
     pub castle: bool,
+    pub check: bool,
+    pub checkmate: bool,
+    pub disambiguation: Disambiguation,
+}
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum MoveKind {
+    Quiet,
+    Capture,
+    EnPassant,
+    Castle,
+    DoublePush,
+}
+
+// a move as generated, before it is played; promotions can be quiet or captures
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub struct Move {
+    pub from: Position,
+    pub to: Position,
+    pub promotion: Option<Name>,
+    pub kind: MoveKind,
 }
 
 #[derive(Debug,Clone)]
@@ -45,11 +77,16 @@ pub struct Piece {
     pub data: Box<dyn PieceData>,
 }
 
-pub trait PieceData: PieceDataClone + Debug + Any {
+// send and sync so games can be handed to a search thread
+pub trait PieceData: PieceDataClone + Debug + Any + Send + Sync {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn legal_moves(&self, pos: Position, color: Color, board: &Board) -> Vec<Position>;
     fn on_move(&mut self, from: Position, to: Position, color: Color, board: &mut Board) -> Option<MoveMeta>;
+
+    // only pieces whose first move matters keep track, undoing a move restores it
+    fn has_moved(&self) -> bool { false }
+    fn set_moved(&mut self, _moved: bool) -> () {}
 }
 
 pub trait PieceDataClone {
@@ -72,6 +109,17 @@ impl Clone for Box<dyn PieceData> {
 }
 
 impl Piece {
+    pub fn new (name: Name, pos: Position, color: Color) -> Piece {
+        match name {
+            Name::Pawn => pawn::new(pos, color),
+            Name::King => king::new(pos, color),
+            Name::Queen => queen::new(pos, color),
+            Name::Rook => rook::new(pos, color),
+            Name::Knight => knight::new(pos, color),
+            Name::Bishop => bishop::new(pos, color),
+        }
+    }
+
     pub fn legal_moves (&self, board: &Board) -> Vec<Position> {
         self.data.legal_moves(self.pos, self.color, board)
     }
@@ -81,7 +129,120 @@ impl Piece {
     }
 }
 
+impl Name {
+    // in index order, which is also cheapest first
+    pub const ALL: [Name; 6] = [Name::Pawn, Name::Knight, Name::Bishop, Name::Rook, Name::Queen, Name::King];
+
+    // slot in per-piece tables such as the board's bitboards
+    pub fn index (&self) -> usize {
+        *self as usize
+    }
+
+    pub fn letter (&self) -> char {
+        match self {
+            Name::Pawn => 'P',
+            Name::King => 'K',
+            Name::Queen => 'Q',
+            Name::Rook => 'R',
+            Name::Knight => 'N',
+            Name::Bishop => 'B',
+        }
+    }
+
+    pub fn from_letter (letter: char) -> Option<Name> {
+        match letter {
+            'P' => Some(Name::Pawn),
+            'K' => Some(Name::King),
+            'Q' => Some(Name::Queen),
+            'R' => Some(Name::Rook),
+            'N' => Some(Name::Knight),
+            'B' => Some(Name::Bishop),
+            _ => None,
+        }
+    }
+}
+
+impl MoveMeta {
+    pub fn to_uci (&self) -> String {
+        lan::write(self.from, self.to, self.promotion)
+    }
+}
+
+impl Move {
+    pub fn is_capture (&self) -> bool {
+        matches!(self.kind, MoveKind::Capture | MoveKind::EnPassant)
+    }
+}
+
+// UCI notation, SAN needs the position and comes from MoveMeta instead
+impl fmt::Display for Move {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", lan::write(self.from, self.to, self.promotion))
+    }
+}
+
+impl fmt::Display for MoveMeta {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.castle {
+            let side = if self.to.col > self.from.col { "O-O" } else { "O-O-O" };
+            write!(f, "{}", side)?;
+        } else {
+            if self.piece_name != Name::Pawn {
+                write!(f, "{}", self.piece_name.letter())?;
+            }
+
+            match self.disambiguation {
+                _ if self.piece_name == Name::Pawn && self.capture => write!(f, "{}", self.from.file())?,
+                Disambiguation::None => {},
+                Disambiguation::File => write!(f, "{}", self.from.file())?,
+                Disambiguation::Rank => write!(f, "{}", self.from.rank())?,
+                Disambiguation::Square => write!(f, "{}", self.from)?,
+            }
+
+            if self.capture {
+                write!(f, "x")?;
+            }
+            write!(f, "{}", self.to)?;
+
+            if let Some(promotion) = self.promotion {
+                write!(f, "={}", promotion.letter())?;
+            }
+        }
+
+        if self.checkmate {
+            write!(f, "#")
+        } else if self.check {
+            write!(f, "+")
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl Position {
+    // row 0 is the eighth rank, col 0 the a-file
+    pub fn from_algebraic (square: &str) -> Option<Position> {
+        let mut chars = square.chars();
+        let file = chars.next()?;
+        let rank = chars.next()?;
+        if chars.next().is_some() || !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
+            return None;
+        }
+
+        Some(Position {
+            row: 8 - rank.to_digit(10)? as usize,
+            col: (file as u8 - b'a') as usize,
+        })
+    }
+
+    pub fn file (&self) -> char {
+        (b'a' + self.col as u8) as char
+    }
+
+    pub fn rank (&self) -> char {
+        (b'8' - self.row as u8) as char
+    }
+
     pub fn shifted (&self, dx: i8, dy: i8) -> Option<Position> {
         let ri = self.row as i8 + dy;
         let ci = self.col as i8 + dx;
@@ -98,6 +259,10 @@ impl Position {
 }
 
 impl Color {
+    pub fn index (&self) -> usize {
+        *self as usize
+    }
+
     pub fn opposite (&self) -> Color {
         match self {
             Color::White => Color::Black,
@@ -105,3 +270,9 @@ impl Color {
         }
     }
 }
+
+impl fmt::Display for Position {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
+}
@@ -1,6 +1,7 @@
 use std::any::Any;
 
-use crate::piece::{Color, MoveMeta, Name, PieceData, Position};
+use crate::piece::{Color, Disambiguation, MoveMeta, Name, Piece, PieceData, Position};
+use crate::bitboard::{pawn_attacks, positions};
 use crate::board::Board;
 use crate::utils::validate_pos;
 
@@ -13,6 +14,8 @@ pub struct PawnData {
 impl PieceData for PawnData {
     fn as_any (&self) -> &dyn Any { self }
     fn as_any_mut (&mut self) -> &mut dyn Any { self }
+    fn has_moved (&self) -> bool { self.has_moved }
+    fn set_moved (&mut self, moved: bool) -> () { self.has_moved = moved; }
 
     fn legal_moves (&self, pos: Position, color: Color, board: &Board) -> Vec<Position> {
         let mut moves = Vec::new();
@@ -23,30 +26,19 @@ impl PieceData for PawnData {
         };
 
         let row = pos.row as i8 + dir;
-        if validate_pos(row as i8, pos.col as i8) {
-            if board.is_empty_cell(row, pos.col as i8) {
-                moves.push(Position { row: row as usize, col: pos.col });
-
-                if !self.has_moved {
-                    let two_rows_forward = pos.row as i8 + 2 * dir;
-                    if validate_pos(two_rows_forward as i8, pos.col as i8) {
-                        if board.is_empty_cell(two_rows_forward, pos.col as i8) {
-                            moves.push(Position { row: two_rows_forward as usize, col: pos.col });
-                        }
-                    }
-                }
-            }
+        if validate_pos(row, pos.col as i8) && board.is_empty_cell(row, pos.col as i8) {
+            moves.push(Position { row: row as usize, col: pos.col });
 
-            for dc in [-1, 1] {
-                let col = pos.col as i8 + dc;
-                if validate_pos(row, col) {
-                    if board.is_enemy_cell(row, col, color) {
-                        moves.push(Position { row: row as usize, col: col as usize });
-                    }
+            if !self.has_moved {
+                let two_rows_forward = pos.row as i8 + 2 * dir;
+                if validate_pos(two_rows_forward, pos.col as i8) && board.is_empty_cell(two_rows_forward, pos.col as i8) {
+                    moves.push(Position { row: two_rows_forward as usize, col: pos.col });
                 }
             }
         }
 
+        moves.extend(positions(pawn_attacks(pos, color) & board.occupancy(color.opposite())));
+
         if let Some(target) = self.passant_target {
             moves.push(target);
         }
@@ -65,38 +57,31 @@ impl PieceData for PawnData {
             Color::Black => -1,
         };
 
-        if from.row == start_rank {
+        if from.row == start_rank && from.row.abs_diff(to.row) == 2 {
             self.calc_passant_target(to, color, board);
         }
 
-        if let Some(passant_target) = self.passant_target {
-            if to == passant_target {
-                board.take(passant_target.row as i8 + dir, passant_target.col as i8);
-            }
+        let mut capture = board.get(to.row as i8, to.col as i8).is_some();
+
+        if let Some(passant_target) = self.passant_target
+            && to == passant_target {
+            capture |= board.take(passant_target.row as i8 + dir, passant_target.col as i8).is_some();
         }
 
-        board.clear_passants(color);
         self.moved();
 
-        let can_promote = match color {
-            Color::White => to.row as i8 - 1 == 0,
-            Color::Black => to.row as i8 + 1 == 7,
-        }; 
-
-        // template
-        // todo: implement promotion prompt(?)
-        // idea: await for user to click button and check if can_promote, if so - put selected
-        // piece
-        let promotion = if can_promote { Some(Name::Queen) } else { None };
-
+        // the promoted piece is swapped in by the game, which knows the chosen name
         Some(MoveMeta {
             piece_name: Name::Pawn,
             piece_color: color,
             from,
             to,
-            capture: board.get(to.row as i8, to.col as i8).is_some(),
-            promotion,
+            capture,
+            promotion: None,
             castle: false,
+            check: false,
+            checkmate: false,
+            disambiguation: Disambiguation::None,
         })
     }
 }
@@ -111,22 +96,37 @@ impl PawnData {
             Color::White => 1,
             Color::Black => -1,
         };
-        let row = pos.row as i8 + dir;
+        let target = Position {
+            row: (pos.row as i8 + dir) as usize,
+            col: pos.col,
+        };
 
         for dc in [-1, 1] {
             let col = pos.col as i8 + dc;
-            if validate_pos(row, col) {
-                if let Some(p) = board.get_mut(row, col) {
-                    if p.name == Name::Pawn && p.color != color {
-                        if let Some(pawn_data) = p.data.as_any_mut().downcast_mut::<PawnData>() {
-                            pawn_data.passant_target = Some(Position {
-                                row: row as usize,
-                                col: pos.col,
-                            });
-                        }
-                    }
-                }
+            if let Some(p) = board.get_mut(pos.row as i8, col)
+                && p.name == Name::Pawn && p.color != color
+                && let Some(pawn_data) = p.data.as_any_mut().downcast_mut::<PawnData>() {
+                pawn_data.passant_target = Some(target);
             }
         }
     }
 }
+
+pub fn promotion_row (color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 7,
+    }
+}
+
+pub fn new (pos: Position, color: Color) -> Piece {
+    Piece {
+        name: Name::Pawn,
+        color,
+        pos,
+        data: Box::new(PawnData {
+            has_moved: false,
+            passant_target: None,
+        })
+    }
+}
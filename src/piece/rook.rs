@@ -1,10 +1,10 @@
-use crate::utils::sliding_moves;
+use crate::bitboard::{rook_attacks, positions};
 use crate::piece::{Color, MoveMeta, Name, Piece, PieceData, Position};
 use crate::board::Board;
 
 use std::any::Any;
 
-static DIRS: &[(i8, i8)] = &[
+pub static DIRS: &[(i8, i8)] = &[
     (1, 0),
     (-1, 0),
     (0, 1),
@@ -19,9 +19,11 @@ pub struct RookData {
 impl PieceData for RookData {
     fn as_any (&self) -> &dyn Any { self }
     fn as_any_mut (&mut self) -> &mut dyn Any { self }
+    fn has_moved (&self) -> bool { self.has_moved }
+    fn set_moved (&mut self, moved: bool) -> () { self.has_moved = moved; }
 
     fn legal_moves (&self, pos: Position, color: Color, board: &Board) -> Vec<Position> {
-        sliding_moves(board, pos, DIRS, Some(color))
+        positions(rook_attacks(pos, board.occupied()) & !board.occupancy(color)).collect()
     }
 
     fn on_move (&mut self, _from: Position, _to: Position, _color: Color, _board: &mut Board) -> Option<MoveMeta> {
@@ -0,0 +1,64 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::search::Search;
+
+// what the uci and xboard engines have in common: writing to the GUI, searching on
+// a thread of their own and deciding how long to think
+
+// kept back from the clock for the move to reach the GUI
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+// the clock is shared out as if this many moves were left when the GUI does not say
+pub const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+// a GUI that went away is not worth failing over, so write errors are dropped
+pub fn send<W: Write> (out: &Mutex<W>, line: &str) -> () {
+    let mut out = out.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let _ = writeln!(out, "{}", line);
+    let _ = out.flush();
+}
+
+// a search going on its own thread, think gets the search and whatever it returns is
+// handed back by join
+pub struct Running<T> {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<T>,
+}
+
+impl<T: Send + 'static> Running<T> {
+    pub fn spawn (search: Search, think: impl FnOnce(Search) -> T + Send + 'static) -> Running<T> {
+        Running {
+            stop: search.stop_flag(),
+            handle: thread::spawn(move || think(search)),
+        }
+    }
+
+    // cuts the search short, join still has to be called to wait for it
+    pub fn stop (&self) -> () {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    // none if the thread panicked
+    pub fn join (self) -> Option<T> {
+        self.handle.join().ok()
+    }
+}
+
+// a fixed time for the move
+pub fn move_time (time: Duration) -> Duration {
+    at_least_a_move(time.saturating_sub(MOVE_OVERHEAD))
+}
+
+// a share of what is left on the clock, with most of the increment spent as it comes
+pub fn allot (clock: Duration, increment: Duration, moves_to_go: u32) -> Duration {
+    let share = clock / moves_to_go.max(1) + increment * 3 / 4;
+    at_least_a_move(share.min(clock.saturating_sub(MOVE_OVERHEAD)))
+}
+
+// even with no time left, a move has to come out
+fn at_least_a_move (time: Duration) -> Duration {
+    time.max(Duration::from_millis(1))
+}
@@ -1,56 +1,105 @@
-use crate::board::Board;
+use crate::board::{Board, PositionError};
 use crate::utils::all_positions;
-use crate::piece::{Color, Name, Position};
-
-pub fn is_in_check (board: &Board, color: Color) -> bool {
-    let king_pos = match board.get_king_pos(color) {
-        Some(k) => k,
-        None => panic!("No {:?} king on board", color),
-    };
-
-    all_positions()
-        .filter_map(|pos| board.get(pos.row as i8, pos.col as i8).map(|p| (pos, p)))
-        .any(|(_pos, p)| {
-            p.color != color && p.name != Name::King && p.legal_moves(board).contains(&king_pos)
-        })
+use crate::bitboard::positions;
+use crate::piece::{pawn, Color, Move, MoveKind, Name, Position};
+
+const PROMOTIONS: [Name; 4] = [Name::Queen, Name::Rook, Name::Bishop, Name::Knight];
+
+pub fn is_in_check (board: &Board, color: Color) -> Result<bool, PositionError> {
+    let king_pos = board.get_king_pos(color).ok_or(PositionError::MissingKing(color))?;
+    Ok(board.is_attacked(king_pos, color.opposite()))
 }
 
-pub fn is_checkmate (board: &mut Board, color: Color) -> bool {
-    if !is_in_check(board, color) {
-        return false;
-    }
+pub fn is_checkmate (board: &mut Board, color: Color) -> Result<bool, PositionError> {
+    Ok(is_in_check(board, color)? && !has_legal_moves(board, color))
+}
 
-    all_positions()
-        .filter_map(|from| board.get(from.row as i8, from.col as i8))
-        .filter(|p| p.color == color)
-        .all(|p| {
-            let mut m = p.legal_moves(board);
-            filter_moves(board, &mut m, p.pos, color);
-            m.is_empty()
-        })
+pub fn is_stalemate (board: &mut Board, color: Color) -> Result<bool, PositionError> {
+    Ok(!is_in_check(board, color)? && !has_legal_moves(board, color))
 }
 
-pub fn is_stalemate (board: &Board, color: Color) -> bool {
-    if is_in_check(board, color) {
-        return false;
+pub fn has_legal_moves (board: &mut Board, color: Color) -> bool {
+    for from in positions(board.occupancy(color)) {
+        let mut targets = match board.get(from.row as i8, from.col as i8) {
+            Some(p) => p.legal_moves(board),
+            None => continue,
+        };
+        filter_moves(board, &mut targets, from, color);
+        if !targets.is_empty() { return true; }
     }
 
-    all_positions()
-        .filter_map(|from| board.get(from.row as i8, from.col as i8))
-        .filter(|p| p.color == color)
-        .all(|p| {
-            let mut m = p.legal_moves(board);
-            filter_moves(board, &mut m, p.pos, color);
-            m.is_empty()
-        })
+    false
 }
 
-pub fn filter_moves (board: &Board, moves: &mut Vec<Position>, from: Position, color: Color) -> () {
-    moves.retain(|&mv| {
-        let mut b_clone = board.clone();
-        if let Some(p) = b_clone.take(from.row as i8, from.col as i8) {
-            b_clone.set(mv.row as i8, mv.col as i8, Some(p));
+// tries each move on the board itself and takes it back again afterwards
+pub fn filter_moves (board: &mut Board, moves: &mut Vec<Position>, from: Position, color: Color) -> () {
+    moves.retain(|&to| match board.make_move(from, to, None) {
+        Some(undo) => {
+            // without a king there is nothing to expose
+            let safe = is_in_check(board, color) != Ok(true);
+            board.unmake_move(undo);
+            safe
         }
-        !is_in_check(&b_clone, color)
+        None => false,
     });
 }
+
+// every legal move for one side, with promotions expanded to each piece
+pub fn legal_moves (board: &mut Board, color: Color) -> Vec<Move> {
+    let mut moves = Vec::new();
+    let passant = board.passant_target();
+
+    for from in positions(board.occupancy(color)) {
+        let (name, mut targets) = match board.get(from.row as i8, from.col as i8) {
+            Some(p) => (p.name, p.legal_moves(board)),
+            None => continue,
+        };
+        filter_moves(board, &mut targets, from, color);
+
+        for to in targets {
+            let kind = if board.is_enemy_cell(to.row as i8, to.col as i8, color) {
+                MoveKind::Capture
+            } else if name == Name::Pawn && Some(to) == passant {
+                MoveKind::EnPassant
+            } else if name == Name::Pawn && from.row.abs_diff(to.row) == 2 {
+                MoveKind::DoublePush
+            } else if name == Name::King && from.col.abs_diff(to.col) == 2 {
+                MoveKind::Castle
+            } else {
+                MoveKind::Quiet
+            };
+
+            if name == Name::Pawn && to.row == pawn::promotion_row(color) {
+                moves.extend(PROMOTIONS.iter().map(|&promotion| Move { from, to, promotion: Some(promotion), kind }));
+            } else {
+                moves.push(Move { from, to, promotion: None, kind });
+            }
+        }
+    }
+
+    moves
+}
+
+pub fn is_insufficient_material (board: &Board) -> bool {
+    let pieces = all_positions()
+        .filter_map(|pos| board.get(pos.row as i8, pos.col as i8))
+        .filter(|p| p.name != Name::King);
+
+    let mut bishop_shades = Vec::new();
+    let mut knights = 0;
+
+    for p in pieces {
+        match p.name {
+            Name::Knight => knights += 1,
+            Name::Bishop => bishop_shades.push((p.pos.row + p.pos.col) % 2),
+            _ => return false,
+        }
+    }
+
+    match (knights, bishop_shades.len()) {
+        (0, 0) | (1, 0) | (0, 1) => true,
+        // any number of bishops, all travelling on the same shade
+        (0, _) => bishop_shades.iter().all(|&shade| shade == bishop_shades[0]),
+        _ => false,
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use crate::game::{Game, MoveError};
+use crate::piece::{Name, Position};
+use crate::bitboard::positions;
+
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub enum SanError {
+    Malformed(String),
+    NoSuchMove(String),
+    Ambiguous(String),
+    Move(MoveError),
+}
+
+impl fmt::Display for SanError {
+    fn fmt (&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Malformed(s) => write!(f, "'{}' is not a SAN move", s),
+            SanError::NoSuchMove(s) => write!(f, "'{}' matches no legal move", s),
+            SanError::Ambiguous(s) => write!(f, "'{}' matches more than one legal move", s),
+            SanError::Move(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+enum SanMove {
+    Castle { kingside: bool },
+    Normal {
+        name: Name,
+        file: Option<usize>,
+        row: Option<usize>,
+        capture: bool,
+        to: Position,
+        promotion: Option<Name>,
+    },
+}
+
+// resolves a SAN string against the legal moves of the side to move
+pub fn resolve (game: &Game, san: &str) -> Result<(Position, Position, Option<Name>), SanError> {
+    let turn = game.get_turn();
+
+    match parse(san)? {
+        SanMove::Castle { kingside } => {
+            let from = game.board.get_king_pos(turn)
+                .ok_or_else(|| SanError::NoSuchMove(san.to_string()))?;
+            let to = from.shifted(if kingside { 2 } else { -2 }, 0)
+                .filter(|to| game.legal_targets(from).contains(to))
+                .ok_or_else(|| SanError::NoSuchMove(san.to_string()))?;
+            Ok((from, to, None))
+        }
+        SanMove::Normal { name, file, row, capture, to, promotion } => {
+            // a pawn changing files is always a capture, en passant leaves the target empty
+            let occupied = game.board.get(to.row as i8, to.col as i8).is_some();
+            let captures = |from: Position| occupied || (name == Name::Pawn && from.col != to.col);
+            let candidates: Vec<Position> = positions(game.board.pieces(name, turn))
+                .filter(|pos| file.is_none_or(|f| f == pos.col) && row.is_none_or(|r| r == pos.row))
+                .filter(|&pos| captures(pos) == capture)
+                .filter(|&pos| game.legal_targets(pos).contains(&to))
+                .collect();
+
+            match candidates.as_slice() {
+                [from] => Ok((*from, to, promotion)),
+                [] => Err(SanError::NoSuchMove(san.to_string())),
+                _ => Err(SanError::Ambiguous(san.to_string())),
+            }
+        }
+    }
+}
+
+fn parse (san: &str) -> Result<SanMove, SanError> {
+    let malformed = || SanError::Malformed(san.to_string());
+    let body = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+    match body {
+        "O-O" | "0-0" => return Ok(SanMove::Castle { kingside: true }),
+        "O-O-O" | "0-0-0" => return Ok(SanMove::Castle { kingside: false }),
+        _ => {},
+    }
+
+    let mut chars: Vec<char> = body.chars().collect();
+
+    let name = match chars.first() {
+        Some(&c) if c.is_ascii_uppercase() => {
+            chars.remove(0);
+            Name::from_letter(c).filter(|&n| n != Name::Pawn).ok_or_else(malformed)?
+        }
+        Some(_) => Name::Pawn,
+        None => return Err(malformed()),
+    };
+
+    // promotion comes as "e8=Q" or the older "e8Q"
+    let promotion = match chars.last() {
+        Some(&c) if c.is_ascii_uppercase() => {
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+            Some(Name::from_letter(c).ok_or_else(malformed)?)
+        }
+        _ => None,
+    };
+    if promotion.is_some() && name != Name::Pawn {
+        return Err(malformed());
+    }
+
+    if chars.len() < 2 {
+        return Err(malformed());
+    }
+    let target: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let to = Position::from_algebraic(&target).ok_or_else(malformed)?;
+    // the x of a capture goes right before the target and nowhere else
+    let capture = chars.last() == Some(&'x');
+    if capture {
+        chars.pop();
+    }
+
+    let mut file = None;
+    let mut row = None;
+    for c in chars {
+        match c {
+            'a'..='h' if file.is_none() && row.is_none() => file = Some((c as u8 - b'a') as usize),
+            '1'..='8' if row.is_none() => row = Some((b'8' - c as u8) as usize),
+            _ => return Err(malformed()),
+        }
+    }
+    // a pawn capture names the file it comes from, exd5 and never xd5
+    if name == Name::Pawn && (capture != file.is_some() || row.is_some()) {
+        return Err(malformed());
+    }
+
+    Ok(SanMove::Normal { name, file, row, capture, to, promotion })
+}
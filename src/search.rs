@@ -0,0 +1,391 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, MoveUndo};
+use crate::eval::Evaluator;
+use crate::game::Game;
+use crate::ordering::{is_quiet, mvv_lva, see, History, Killers, MovePicker};
+use crate::piece::{Color, Move, Name};
+use crate::rules::{is_in_check, legal_moves};
+use crate::transposition::{Bound, TranspositionTable};
+use crate::zobrist;
+
+pub const INFINITY: i32 = 32_000;
+// mate scores count down from here by the number of plies to the mate
+pub const MATE: i32 = 31_000;
+
+pub const MAX_PLY: usize = 128;
+
+// any combination of limits may be set, the search stops at whichever runs out first;
+// with none at all it runs until the stop flag is raised
+#[derive(Debug,Clone,Copy,Default)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+// scores are in centipawns from the point of view of the side to move
+#[derive(Debug,Clone,Default)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub pv: Vec<Move>,
+}
+
+impl SearchResult {
+    // moves until mate, negative when the side to move is the one getting mated
+    pub fn mate_in (&self) -> Option<i32> {
+        if self.score.abs() < MATE - MAX_PLY as i32 {
+            return None;
+        }
+
+        let plies = MATE - self.score.abs();
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+// the clock is only read every so many nodes, it is comparatively slow
+const CLOCK_INTERVAL: u64 = 256;
+
+pub struct Search {
+    limits: Limits,
+    stop: Arc<AtomicBool>,
+    // set when this run's limits ran out, unlike the stop flag it is cleared for every run
+    halted: bool,
+    start: Instant,
+    nodes: u64,
+    // node count at which the clock is next read
+    next_clock: u64,
+    // position hashes from the start of the game down to the current node
+    keys: Vec<u64>,
+    // halfmove clock at each ply, for the fifty-move rule inside the tree
+    clocks: Vec<u32>,
+    undos: Vec<MoveUndo>,
+    evaluator: Evaluator,
+    table: Arc<TranspositionTable>,
+    killers: Killers,
+    history: History,
+}
+
+impl Search {
+    // new and with_evaluator each allocate a table of their own, a search that is one of
+    // several, or one move of a game, should be handed the shared one through with_table
+    pub fn new (limits: Limits) -> Search {
+        Search::with_evaluator(limits, Evaluator::default())
+    }
+
+    pub fn with_evaluator (limits: Limits, evaluator: Evaluator) -> Search {
+        Search::with_table(limits, evaluator, Arc::new(TranspositionTable::default()))
+    }
+
+    // lets several searches, or one search across several moves of a game, share what they learnt
+    pub fn with_table (limits: Limits, evaluator: Evaluator, table: Arc<TranspositionTable>) -> Search {
+        Search {
+            limits,
+            stop: Arc::new(AtomicBool::new(false)),
+            halted: false,
+            start: Instant::now(),
+            nodes: 0,
+            next_clock: 0,
+            keys: Vec::new(),
+            clocks: Vec::new(),
+            undos: Vec::new(),
+            evaluator,
+            table,
+            killers: Killers::default(),
+            history: History::default(),
+        }
+    }
+
+    pub fn table (&self) -> Arc<TranspositionTable> {
+        Arc::clone(&self.table)
+    }
+
+    // raising this flag from another thread ends the search at the next check,
+    // it stays raised for later runs until whoever raised it lowers it again
+    pub fn stop_flag (&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    pub fn run (&mut self, game: &Game) -> SearchResult {
+        self.run_with(game, |_| ())
+    }
+
+    // like run, reporting the result of every completed iteration along the way
+    pub fn run_with (&mut self, game: &Game, mut report: impl FnMut(&SearchResult)) -> SearchResult {
+        self.start = Instant::now();
+        self.halted = false;
+        self.nodes = 0;
+        self.next_clock = CLOCK_INTERVAL;
+        self.keys = game.position_history().to_vec();
+        // a game built by hand may not have recorded its starting position
+        if self.keys.is_empty() {
+            self.keys.push(game.hash());
+        }
+        self.clocks = vec![game.get_halfmove_clock()];
+        self.table.new_search();
+        self.killers.clear();
+        self.history.age();
+
+        let mut board = game.board.clone();
+        let turn = game.get_turn();
+        let mut result = SearchResult::default();
+        // a finished game or an invalid board has no move to offer
+        if !game.status().is_playable() {
+            return result;
+        }
+
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY as u32).min(MAX_PLY as u32);
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = self.negamax(&mut board, turn, depth, 0, -INFINITY, INFINITY, &result.pv, &mut pv);
+            self.extend_pv(&mut board, turn, depth as usize, &mut pv);
+
+            // a cut-off iteration has not looked at every move, it is never reported
+            if self.stopped() {
+                break;
+            }
+
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score,
+                depth,
+                nodes: self.nodes,
+                elapsed: self.start.elapsed(),
+                pv,
+            };
+            report(&result);
+
+            if self.stopped() || self.out_of_time() || result.best_move.is_none() || result.mate_in().is_some() {
+                break;
+            }
+        }
+
+        // stopped before even one move was searched, any legal move beats none
+        if result.best_move.is_none() {
+            result.best_move = legal_moves(&mut board, turn).first().copied();
+        }
+
+        result.nodes = self.nodes;
+        result.elapsed = self.start.elapsed();
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn negamax (&mut self, board: &mut Board, color: Color, depth: u32, ply: usize,
+                mut alpha: i32, beta: i32, prev_pv: &[Move], pv: &mut Vec<Move>) -> i32 {
+        self.nodes += 1;
+
+        if ply > 0 && self.is_draw() {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(board, color, ply, alpha, beta);
+        }
+
+        let key = self.key();
+        let entry = self.table.probe(key, ply);
+        // the root always searches, it has to come up with a move
+        if ply > 0 && let Some(entry) = entry && entry.depth >= depth {
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if usable {
+                return entry.score;
+            }
+        }
+
+        let moves = legal_moves(board, color);
+        if moves.is_empty() {
+            return if is_in_check(board, color) == Ok(true) { -MATE + ply as i32 } else { 0 };
+        }
+
+        // the previous iteration's line goes first, it is the most likely best,
+        // off that line the move remembered from earlier searches of this position does
+        let hash_move = prev_pv.first().copied().or(entry.and_then(|entry| entry.best_move));
+        let mut picker = MovePicker::new(moves, hash_move, self.killers.get(ply), color);
+
+        let original_alpha = alpha;
+        let mut best_move = None;
+        let mut child_pv = Vec::new();
+        while let Some(mv) = picker.next(board, &self.history) {
+            let follow = if prev_pv.first() == Some(&mv) { &prev_pv[1..] } else { &[] };
+
+            self.push(board, mv, color);
+            child_pv.clear();
+            let score = -self.negamax(board, color.opposite(), depth - 1, ply + 1, -beta, -alpha, follow, &mut child_pv);
+            self.pop(board);
+
+            if self.stopped() {
+                return 0;
+            }
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(mv);
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+            if alpha >= beta {
+                if is_quiet(&mv) {
+                    self.killers.add(ply, mv);
+                    self.history.add(color, &mv, depth);
+                }
+                break;
+            }
+        }
+
+        let bound = if alpha >= beta {
+            Bound::Lower
+        } else if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.table.store(key, depth, bound, alpha, best_move, ply);
+
+        alpha
+    }
+
+    // only captures and promotions, so that the static evaluation is never taken mid-exchange
+    fn quiescence (&mut self, board: &mut Board, color: Color, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+
+        let in_check = is_in_check(board, color) == Ok(true);
+        let stand_pat = self.evaluator.evaluate(board, color);
+
+        if ply >= MAX_PLY {
+            return stand_pat;
+        }
+        // in check every evasion has to be looked at, standing still is not an option
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+
+        let mut moves = legal_moves(board, color);
+        if moves.is_empty() && in_check {
+            return -MATE + ply as i32;
+        }
+        // captures that lose material are not going to lift the score above standing pat
+        if !in_check {
+            moves.retain(|mv| !is_quiet(mv) && see(board, mv) >= 0);
+        }
+        moves.sort_by_key(|mv| -mvv_lva(board, mv));
+
+        for mv in moves {
+            self.push(board, mv, color);
+            let score = -self.quiescence(board, color.opposite(), ply + 1, -beta, -alpha);
+            self.pop(board);
+
+            if self.stopped() {
+                return 0;
+            }
+
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        alpha
+    }
+
+    // a line cut short by a table hit is continued with the best moves the table remembers
+    fn extend_pv (&mut self, board: &mut Board, color: Color, depth: usize, pv: &mut Vec<Move>) -> () {
+        let mut color = color;
+        for &mv in pv.iter() {
+            self.push(board, mv, color);
+            color = color.opposite();
+        }
+
+        while pv.len() < depth {
+            let key = self.key();
+            let next = self.table.probe(key, 0)
+                .and_then(|entry| entry.best_move)
+                .filter(|mv| legal_moves(board, color).contains(mv));
+            let Some(mv) = next else { break };
+
+            pv.push(mv);
+            self.push(board, mv, color);
+            color = color.opposite();
+        }
+
+        for _ in 0..pv.len() {
+            self.pop(board);
+        }
+    }
+
+    fn push (&mut self, board: &mut Board, mv: Move, color: Color) -> () {
+        let undo = board.make_move(mv.from, mv.to, mv.promotion).expect("legal move from an empty square");
+        let meta = undo.meta();
+        let clock = if meta.capture || meta.piece_name == Name::Pawn { 0 } else { self.clock() + 1 };
+
+        self.undos.push(undo);
+        self.clocks.push(clock);
+        self.keys.push(match color.opposite() {
+            Color::White => board.hash(),
+            Color::Black => board.hash() ^ zobrist::side(),
+        });
+    }
+
+    fn pop (&mut self, board: &mut Board) -> () {
+        if let Some(undo) = self.undos.pop() {
+            board.unmake_move(undo);
+        }
+        self.clocks.pop();
+        self.keys.pop();
+    }
+
+    fn key (&self) -> u64 {
+        self.keys.last().copied().unwrap_or_default()
+    }
+
+    fn clock (&self) -> u32 {
+        self.clocks.last().copied().unwrap_or_default()
+    }
+
+    // a single repetition is enough inside the tree, the line can always be repeated again
+    fn is_draw (&self) -> bool {
+        let clock = self.clock();
+        if clock >= 100 {
+            return true;
+        }
+
+        let current = self.key();
+        self.keys.iter().rev().skip(1).take(clock as usize).any(|&key| key == current)
+    }
+
+    fn stopped (&mut self) -> bool {
+        if self.halted || self.stop.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        let out_of_nodes = self.limits.nodes.is_some_and(|max| self.nodes >= max);
+        let out_of_time = self.nodes >= self.next_clock && {
+            self.next_clock = self.nodes + CLOCK_INTERVAL;
+            self.out_of_time()
+        };
+
+        self.halted = out_of_nodes || out_of_time;
+        self.halted
+    }
+
+    fn out_of_time (&mut self) -> bool {
+        self.halted |= self.limits.time.is_some_and(|max| self.start.elapsed() >= max);
+        self.halted
+    }
+}
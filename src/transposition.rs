@@ -0,0 +1,223 @@
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+
+use crate::bitboard::{position, square};
+use crate::piece::{Move, MoveKind, Name};
+use crate::search::{MATE, MAX_PLY};
+
+pub const DEFAULT_MEGABYTES: usize = 16;
+
+// in MoveKind order, for unpacking
+const KINDS: [MoveKind; 5] = [MoveKind::Quiet, MoveKind::Capture, MoveKind::EnPassant, MoveKind::Castle, MoveKind::DoublePush];
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum Bound {
+    // the score is exact
+    Exact,
+    // the search failed high, the real score is at least this
+    Lower,
+    // the search failed low, the real score is at most this
+    Upper,
+}
+
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub struct Entry {
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Move>,
+    pub age: u8,
+}
+
+// the key is stored xored with the data, so a slot torn by two threads writing at once
+// no longer matches its key and reads as empty instead of handing out a mix of two entries
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+pub struct TranspositionTable {
+    slots: Box<[Slot]>,
+    // bumped every search, entries left over from older searches are the first to go
+    age: AtomicU8,
+}
+
+impl Default for TranspositionTable {
+    fn default () -> TranspositionTable {
+        TranspositionTable::new(DEFAULT_MEGABYTES)
+    }
+}
+
+impl TranspositionTable {
+    // the slot count is rounded down to a power of two, at least one slot is always kept
+    pub fn new (megabytes: usize) -> TranspositionTable {
+        let wanted = (megabytes * 1024 * 1024 / size_of::<Slot>()).max(1);
+        let len = 1 << wanted.ilog2();
+
+        TranspositionTable {
+            slots: (0..len).map(|_| Slot::default()).collect(),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    pub fn len (&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty (&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    // forgets everything, between games
+    pub fn clear (&self) -> () {
+        for slot in self.slots.iter() {
+            slot.check.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    pub fn new_search (&self) -> () {
+        // only six bits of age are stored
+        let age = (self.age.load(Ordering::Relaxed) + 1) & AGE_MASK as u8;
+        self.age.store(age, Ordering::Relaxed);
+    }
+
+    // mate scores come back relative to the given ply
+    pub fn probe (&self, key: u64, ply: usize) -> Option<Entry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        if data == 0 || slot.check.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+
+        let mut entry = unpack(data);
+        entry.score = score_from_table(entry.score, ply);
+        Some(entry)
+    }
+
+    // a slot is given up when it is empty, left over from an older search, holds the same
+    // position or was searched no deeper than this
+    pub fn store (&self, key: u64, depth: u32, bound: Bound, score: i32, best_move: Option<Move>, ply: usize) -> () {
+        let slot = self.slot(key);
+        let age = self.age.load(Ordering::Relaxed);
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let same = old_data != 0 && slot.check.load(Ordering::Relaxed) ^ old_data == key;
+
+        let mut best_move = best_move;
+        if old_data != 0 {
+            let old = unpack(old_data);
+            let replace = same || old.age != age || depth >= old.depth;
+            if !replace {
+                return;
+            }
+            // a fail-low has no best move of its own, the last one known is still the best guess
+            if same && best_move.is_none() {
+                best_move = old.best_move;
+            }
+        }
+
+        let entry = Entry {
+            depth,
+            bound,
+            score: score_to_table(score, ply),
+            best_move,
+            age,
+        };
+        let data = pack(&entry);
+        slot.data.store(data, Ordering::Relaxed);
+        slot.check.store(key ^ data, Ordering::Relaxed);
+    }
+
+    // permille of a sample of slots filled during the current search
+    pub fn hashfull (&self) -> u32 {
+        let age = self.age.load(Ordering::Relaxed);
+        let sample = self.slots.len().min(1000);
+        let used = self.slots[..sample].iter()
+            .filter(|slot| {
+                let data = slot.data.load(Ordering::Relaxed);
+                data != 0 && unpack(data).age == age
+            })
+            .count();
+
+        (used * 1000 / sample) as u32
+    }
+
+    fn slot (&self, key: u64) -> &Slot {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+}
+
+// mates are stored as distance from the stored node rather than from the root,
+// so the same entry reads correctly wherever in the tree it is found again
+fn score_to_table (score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table (score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// bit layout of the data word:
+//  0-15 score, 16-23 depth, 24-25 bound, 26-31 age,
+//  32 has move, 33-38 from, 39-44 to, 45-47 promotion, 48-50 kind, 63 always set
+const AGE_MASK: u64 = 0x3F;
+const OCCUPIED: u64 = 1 << 63;
+
+fn pack (entry: &Entry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    let mv = entry.best_move.map_or(0, |mv| {
+        let promotion = mv.promotion.map_or(0, |name| name.index() as u64 + 1);
+        1 | (square(mv.from) as u64) << 1 | (square(mv.to) as u64) << 7
+            | promotion << 13 | (mv.kind as u64) << 16
+    });
+
+    (entry.score as i16 as u16 as u64)
+        | (entry.depth.min(u8::MAX as u32) as u64) << 16
+        | bound << 24
+        | (entry.age as u64 & AGE_MASK) << 26
+        | mv << 32
+        | OCCUPIED
+}
+
+fn unpack (data: u64) -> Entry {
+    let bound = match (data >> 24) & 3 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    let mv = data >> 32;
+    let best_move = (mv & 1 != 0).then(|| {
+        let promotion = ((mv >> 13) & 7) as usize;
+        Move {
+            from: position(((mv >> 1) & 0x3F) as usize),
+            to: position(((mv >> 7) & 0x3F) as usize),
+            promotion: (promotion > 0).then(|| Name::ALL[promotion - 1]),
+            kind: KINDS[((mv >> 16) & 7) as usize],
+        }
+    });
+
+    Entry {
+        depth: ((data >> 16) & 0xFF) as u32,
+        bound,
+        score: data as u16 as i16 as i32,
+        best_move,
+        age: ((data >> 26) & AGE_MASK) as u8,
+    }
+}
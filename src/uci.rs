@@ -0,0 +1,300 @@
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use crate::bench;
+use crate::eval::{Evaluator, Weights};
+use crate::game::Game;
+use crate::piece::Color;
+use crate::protocol::{self, send, Running, DEFAULT_MOVES_TO_GO};
+use crate::search::{Limits, Search, SearchResult};
+use crate::transposition::{TranspositionTable, DEFAULT_MEGABYTES};
+
+const MAX_HASH: usize = 1024;
+
+// the parameters of a go command, times in milliseconds
+#[derive(Debug,Clone,Copy,Default)]
+pub struct Go {
+    depth: Option<u32>,
+    nodes: Option<u64>,
+    movetime: Option<u64>,
+    wtime: Option<u64>,
+    btime: Option<u64>,
+    winc: Option<u64>,
+    binc: Option<u64>,
+    movestogo: Option<u32>,
+    infinite: bool,
+}
+
+// a UCI engine talking to whatever sits on the other end of out; searches run on
+// their own thread so stop and isready are answered while thinking
+pub struct Uci<W> {
+    out: Arc<Mutex<W>>,
+    game: Game,
+    table: Arc<TranspositionTable>,
+    evaluator: Evaluator,
+    running: Option<Running<()>>,
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+    pub fn new (out: W) -> Uci<W> {
+        Uci {
+            out: Arc::new(Mutex::new(out)),
+            game: Game::new(),
+            table: Arc::new(TranspositionTable::default()),
+            evaluator: Evaluator::default(),
+            running: None,
+        }
+    }
+
+    pub fn output (&self) -> Arc<Mutex<W>> {
+        Arc::clone(&self.out)
+    }
+
+    // until quit or the end of input, which also ends any search still running
+    pub fn run<R: BufRead> (&mut self, input: R) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?) {
+                break;
+            }
+        }
+        self.finish_search();
+
+        Ok(())
+    }
+
+    // one command, false once the engine should quit; unknown commands are ignored
+    pub fn handle (&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("uci") => self.identify(),
+            Some("isready") => send(&self.out, "readyok"),
+            Some("ucinewgame") => {
+                self.finish_search();
+                self.table.clear();
+                self.game = Game::new();
+            }
+            Some("position") => {
+                self.finish_search();
+                self.set_position(&tokens.collect::<Vec<_>>());
+            }
+            Some("go") => {
+                self.finish_search();
+                self.go(&tokens.collect::<Vec<_>>());
+            }
+            Some("stop") => self.finish_search(),
+            Some("setoption") => {
+                self.finish_search();
+                self.set_option(&tokens.collect::<Vec<_>>());
+            }
+            Some("bench") => {
+                self.finish_search();
+                self.bench(tokens.next());
+            }
+            Some("quit") => {
+                self.finish_search();
+                return false;
+            }
+            _ => (),
+        }
+
+        true
+    }
+
+    fn identify (&self) -> () {
+        send(&self.out, &format!("id name chess-rs {}", env!("CARGO_PKG_VERSION")));
+        send(&self.out, "id author the chess-rs developers");
+        send(&self.out, &format!("option name Hash type spin default {} min 1 max {}", DEFAULT_MEGABYTES, MAX_HASH));
+        send(&self.out, "option name Clear Hash type button");
+        send(&self.out, "option name Weights type string default <empty>");
+        send(&self.out, "uciok");
+    }
+
+    // position [startpos | fen <fen>] [moves <move>...]
+    fn set_position (&mut self, args: &[&str]) -> () {
+        let moves_at = args.iter().position(|&arg| arg == "moves").unwrap_or(args.len());
+        let (setup, moves) = args.split_at(moves_at);
+
+        let game = match setup {
+            ["startpos"] => Ok(Game::new()),
+            ["fen", fen @ ..] => Game::from_fen(&fen.join(" ")).map_err(|err| err.to_string()),
+            _ => Err(format!("expected startpos or fen, got '{}'", setup.join(" "))),
+        };
+        let mut game = match game {
+            Ok(game) => game,
+            Err(err) => {
+                send(&self.out, &format!("info string bad position: {}", err));
+                return;
+            }
+        };
+
+        // all or nothing, a half played list is a position the GUI never asked for
+        for mv in moves.iter().skip(1) {
+            if let Err(err) = game.apply_uci(mv) {
+                send(&self.out, &format!("info string bad move {}: {}", mv, err));
+                return;
+            }
+        }
+        self.game = game;
+    }
+
+    fn go (&mut self, args: &[&str]) -> () {
+        let go = parse_go(args);
+        let limits = limits(&go, self.game.get_turn());
+
+        let search = Search::with_table(limits, self.evaluator.clone(), Arc::clone(&self.table));
+
+        let game = self.game.clone();
+        let out = Arc::clone(&self.out);
+        let table = Arc::clone(&self.table);
+        self.running = Some(Running::spawn(search, move |mut search| {
+            let flag = search.stop_flag();
+            let result = search.run_with(&game, |result| send(&out, &info(result, table.hashfull())));
+
+            // an infinite search holds on to its move until told to stop
+            while go.infinite && !flag.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            let best = match (result.best_move, result.pv.get(1)) {
+                (Some(mv), Some(ponder)) => format!("bestmove {} ponder {}", mv, ponder),
+                (Some(mv), None) => format!("bestmove {}", mv),
+                (None, _) => String::from("bestmove 0000"),
+            };
+            send(&out, &best);
+        }));
+    }
+
+    // bench [depth]: searches the fixed suite of positions and reports the node counts,
+    // which stay the same from run to run until the search changes
+    fn bench (&self, depth: Option<&str>) -> () {
+        let depth = depth.and_then(|depth| depth.parse().ok()).unwrap_or(bench::DEFAULT_DEPTH);
+
+        let (nodes, elapsed) = bench::run(depth, |fen, result| {
+            let best = result.best_move.map_or(String::from("0000"), |mv| mv.to_string());
+            send(&self.out, &format!("info string {} nodes {} bestmove {}", fen, result.nodes, best));
+        });
+
+        let millis = elapsed.as_millis() as u64;
+        send(&self.out, &format!("Total time (ms): {}", millis));
+        send(&self.out, &format!("Nodes searched: {}", nodes));
+        send(&self.out, &format!("Nodes/second: {}", nodes * 1000 / millis.max(1)));
+    }
+
+    // setoption name <name> [value <value>], names are case-insensitive
+    fn set_option (&mut self, args: &[&str]) -> () {
+        let value_at = args.iter().position(|&arg| arg == "value").unwrap_or(args.len());
+        let name = args[..value_at].iter().skip(1).copied().collect::<Vec<_>>().join(" ");
+        let value = args.get(value_at + 1..).unwrap_or(&[]).join(" ");
+
+        match name.to_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(megabytes) => self.table = Arc::new(TranspositionTable::new(megabytes.clamp(1, MAX_HASH))),
+                Err(_) => send(&self.out, &format!("info string bad Hash value '{}'", value)),
+            },
+            "clear hash" => self.table.clear(),
+            "weights" => {
+                let weights = if value.is_empty() || value == "<empty>" {
+                    Ok(Weights::default())
+                } else {
+                    Weights::from_file(&value)
+                };
+                match weights {
+                    Ok(weights) => self.evaluator = Evaluator::new(weights),
+                    Err(err) => send(&self.out, &format!("info string bad Weights file '{}': {}", value, err)),
+                }
+            }
+            _ => send(&self.out, &format!("info string unknown option '{}'", name)),
+        }
+    }
+
+    // stops the search in progress, if any, and waits for its bestmove to go out
+    fn finish_search (&mut self) -> () {
+        if let Some(running) = self.running.take() {
+            running.stop();
+            running.join();
+        }
+    }
+}
+
+fn info (result: &SearchResult, hashfull: u32) -> String {
+    let score = match result.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let millis = result.elapsed.as_millis() as u64;
+    let nps = result.nodes * 1000 / millis.max(1);
+    let mut line = format!("info depth {} score {} nodes {} nps {} time {} hashfull {}",
+                           result.depth, score, result.nodes, nps, millis, hashfull);
+    if !result.pv.is_empty() {
+        let pv = result.pv.iter().map(|mv| mv.to_string()).collect::<Vec<_>>();
+        line.push_str(&format!(" pv {}", pv.join(" ")));
+    }
+
+    line
+}
+
+// the arguments of go, anything unknown or malformed is left out
+pub fn parse_go (args: &[&str]) -> Go {
+    let mut go = Go::default();
+    let mut tokens = args.iter();
+
+    while let Some(&token) = tokens.next() {
+        if token == "infinite" {
+            go.infinite = true;
+            continue;
+        }
+
+        let slot = match token {
+            "wtime" => &mut go.wtime,
+            "btime" => &mut go.btime,
+            "winc" => &mut go.winc,
+            "binc" => &mut go.binc,
+            "movetime" => &mut go.movetime,
+            "nodes" => &mut go.nodes,
+            "depth" => {
+                go.depth = tokens.next().and_then(|value| value.parse().ok());
+                continue;
+            }
+            "movestogo" => {
+                go.movestogo = tokens.next().and_then(|value| value.parse().ok());
+                continue;
+            }
+            // ponder, searchmoves and mate are not supported and taken as a plain go
+            _ => continue,
+        };
+        *slot = tokens.next().and_then(|value| value.parse().ok());
+    }
+
+    go
+}
+
+// how far the side to move may search, a movetime overrides the clocks
+pub fn limits (go: &Go, turn: Color) -> Limits {
+    if go.infinite {
+        return Limits::default();
+    }
+
+    let (clock, increment) = match turn {
+        Color::White => (go.wtime, go.winc),
+        Color::Black => (go.btime, go.binc),
+    };
+
+    let time = match (go.movetime, clock) {
+        (Some(movetime), _) => Some(protocol::move_time(Duration::from_millis(movetime))),
+        (None, Some(clock)) => Some(protocol::allot(
+            Duration::from_millis(clock),
+            Duration::from_millis(increment.unwrap_or(0)),
+            go.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO))),
+        (None, None) => None,
+    };
+
+    Limits {
+        depth: go.depth,
+        nodes: go.nodes,
+        time,
+    }
+}
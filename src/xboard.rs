@@ -0,0 +1,290 @@
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::eval::Evaluator;
+use crate::game::{DrawReason, Game, GameStatus};
+use crate::pgn::result_token;
+use crate::piece::{Color, Move};
+use crate::protocol::{self, send, Running, DEFAULT_MOVES_TO_GO};
+use crate::search::{Limits, Search, SearchResult};
+use crate::transposition::TranspositionTable;
+
+// how mates are shown in thinking output, plus or minus the moves to mate
+const MATE_SCORE: i32 = 100_000;
+
+// level <moves per control> <base> <increment>, a moves count of 0 is sudden death
+#[derive(Debug,Clone,Copy)]
+struct Level {
+    moves: u32,
+    base: Duration,
+    increment: Duration,
+}
+
+impl Default for Level {
+    fn default () -> Level {
+        Level { moves: 40, base: Duration::from_secs(300), increment: Duration::ZERO }
+    }
+}
+
+// an engine speaking the chess engine communication protocol (xboard, version 2);
+// it thinks on its own thread and the move it sends is played on the game once collected
+pub struct Xboard<W> {
+    out: Arc<Mutex<W>>,
+    game: Game,
+    table: Arc<TranspositionTable>,
+    // the side the engine plays, none in force mode
+    engine: Option<Color>,
+    post: bool,
+    depth: Option<u32>,
+    // fixed time per move from st, overrides the level
+    move_time: Option<Duration>,
+    level: Level,
+    // what is left on the engine's clock as last told by time
+    clock: Option<Duration>,
+    running: Option<Running<Option<Move>>>,
+    // set when the move the running search is looking for is no longer wanted
+    discard: Arc<AtomicBool>,
+}
+
+impl<W: Write + Send + 'static> Xboard<W> {
+    pub fn new (out: W) -> Xboard<W> {
+        Xboard {
+            out: Arc::new(Mutex::new(out)),
+            game: Game::new(),
+            table: Arc::new(TranspositionTable::default()),
+            engine: Some(Color::Black),
+            post: false,
+            depth: None,
+            move_time: None,
+            level: Level::default(),
+            clock: None,
+            running: None,
+            discard: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn output (&self) -> Arc<Mutex<W>> {
+        Arc::clone(&self.out)
+    }
+
+    // until quit or the end of input; at the end of input a search still going is
+    // allowed to finish, so that scripted sessions see the engine's move
+    pub fn run<R: BufRead> (&mut self, input: R) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?) {
+                return Ok(());
+            }
+        }
+        self.wait_search();
+
+        Ok(())
+    }
+
+    // one command, false once the engine should quit
+    pub fn handle (&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else { return true };
+        let args = tokens.collect::<Vec<_>>();
+
+        // the GUI is allowed to break into a search with these, anything else only
+        // comes while the engine is not thinking and waits for the move if it is
+        match command {
+            "?" => self.stop_search(true),
+            "ping" => (),
+            "new" | "force" | "result" | "undo" | "remove" | "setboard" | "quit" => self.stop_search(false),
+            _ => self.wait_search(),
+        }
+
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "easy" | "hard" | "computer" | "name" | "rating" | "ics" | "?" => (),
+            "protover" => self.features(),
+            "ping" => send(&self.out, &format!("pong {}", args.join(" "))),
+            "new" => {
+                self.game = Game::new();
+                self.table.clear();
+                self.engine = Some(Color::Black);
+                self.depth = None;
+                self.clock = None;
+            }
+            "force" => self.engine = None,
+            "go" => {
+                self.engine = Some(self.game.get_turn());
+                self.think();
+            }
+            "usermove" => self.user_move(args.first().copied().unwrap_or("")),
+            "setboard" => match Game::from_fen(&args.join(" ")) {
+                Ok(game) => self.game = game,
+                Err(err) => send(&self.out, &format!("tellusererror Illegal position: {}", err)),
+            },
+            "undo" => {
+                self.game.undo();
+            }
+            "remove" => {
+                self.game.undo();
+                self.game.undo();
+            }
+            "result" => self.engine = None,
+            "level" => match parse_level(&args) {
+                Some(level) => {
+                    self.level = level;
+                    self.move_time = None;
+                }
+                None => send(&self.out, &format!("Error (bad level): {}", args.join(" "))),
+            },
+            "st" => self.move_time = args.first().and_then(|secs| secs.parse().ok()).map(Duration::from_secs),
+            "sd" => self.depth = args.first().and_then(|depth| depth.parse().ok()),
+            "time" => self.clock = args.first().and_then(|cs| cs.parse::<u64>().ok()).map(|cs| Duration::from_millis(cs * 10)),
+            // the engine plans with its own clock only
+            "otim" => (),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => return false,
+            _ => send(&self.out, &format!("Error (unknown command): {}", command)),
+        }
+
+        true
+    }
+
+    fn features (&self) -> () {
+        send(&self.out, &format!(
+            "feature myname=\"chess-rs {}\" setboard=1 usermove=1 ping=1 playother=0 colors=0 \
+             analyze=0 sigint=0 sigterm=0 reuse=1 done=1",
+            env!("CARGO_PKG_VERSION")));
+    }
+
+    fn user_move (&mut self, mv: &str) -> () {
+        if let Err(err) = self.game.apply_uci(mv) {
+            send(&self.out, &format!("Illegal move ({}): {}", err, mv));
+            return;
+        }
+
+        if let Some(result) = result_line(self.game.status()) {
+            send(&self.out, &result);
+            return;
+        }
+        if self.engine == Some(self.game.get_turn()) {
+            self.think();
+        }
+    }
+
+    fn think (&mut self) -> () {
+        if !self.game.status().is_playable() {
+            return;
+        }
+
+        let search = Search::with_table(self.limits(), Evaluator::default(), Arc::clone(&self.table));
+        self.discard = Arc::new(AtomicBool::new(false));
+
+        let mut game = self.game.clone();
+        let out = Arc::clone(&self.out);
+        let post = self.post;
+        let dropped = Arc::clone(&self.discard);
+        self.running = Some(Running::spawn(search, move |mut search| {
+            let result = search.run_with(&game, |result| {
+                if post {
+                    send(&out, &thinking(result));
+                }
+            });
+
+            // the check and the move go out under one lock, so a move is either
+            // announced and kept or dropped without a word
+            let mut out = out.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mv = result.best_move.filter(|_| !dropped.load(Ordering::Relaxed))?;
+            let _ = writeln!(out, "move {}", mv);
+            if game.apply(mv).is_ok() && let Some(result) = result_line(game.status()) {
+                let _ = writeln!(out, "{}", result);
+            }
+            let _ = out.flush();
+
+            Some(mv)
+        }));
+    }
+
+    // what the next search may spend: st's fixed time if set, otherwise a share of the
+    // engine's clock, or of the level's base time before the GUI has sent one
+    pub fn limits (&self) -> Limits {
+        let time = match self.move_time {
+            Some(time) => protocol::move_time(time),
+            None => {
+                // a moves count of 0 is sudden death
+                let moves_left = match self.level.moves {
+                    0 => DEFAULT_MOVES_TO_GO,
+                    moves => moves - (self.game.get_fullmove_number() - 1) % moves,
+                };
+                protocol::allot(self.clock.unwrap_or(self.level.base), self.level.increment, moves_left)
+            }
+        };
+
+        Limits {
+            depth: self.depth,
+            nodes: None,
+            time: Some(time),
+        }
+    }
+
+    // waits for the engine's move and plays it
+    fn wait_search (&mut self) -> () {
+        if let Some(running) = self.running.take()
+            && let Some(Some(mv)) = running.join() {
+            let _ = self.game.apply(mv);
+        }
+    }
+
+    // cuts the search short, with its move played or thrown away
+    fn stop_search (&mut self, keep_move: bool) -> () {
+        if let Some(running) = &self.running {
+            self.discard.store(!keep_move, Ordering::Relaxed);
+            running.stop();
+        }
+        self.wait_search();
+    }
+}
+
+// ply, score, time in centiseconds, nodes and the principal variation
+fn thinking (result: &SearchResult) -> String {
+    let score = match result.mate_in() {
+        Some(moves) if moves > 0 => MATE_SCORE + moves,
+        Some(moves) => -MATE_SCORE + moves,
+        None => result.score,
+    };
+    let pv = result.pv.iter().map(|mv| mv.to_string()).collect::<Vec<_>>();
+
+    format!("{} {} {} {} {}", result.depth, score, result.elapsed.as_millis() / 10, result.nodes, pv.join(" "))
+}
+
+fn result_line (status: GameStatus) -> Option<String> {
+    let comment = match status {
+        GameStatus::Checkmate(winner) => format!("{:?} mates", winner),
+        GameStatus::Stalemate => String::from("Stalemate"),
+        GameStatus::Draw(reason) => String::from(match reason {
+            DrawReason::Agreement => "Draw agreed",
+            DrawReason::FiftyMoveRule => "Draw by fifty-move rule",
+            DrawReason::SeventyFiveMoveRule => "Draw by seventy-five-move rule",
+            DrawReason::ThreefoldRepetition => "Draw by repetition",
+            DrawReason::FivefoldRepetition => "Draw by fivefold repetition",
+            DrawReason::InsufficientMaterial => "Draw by insufficient material",
+        }),
+        _ => return None,
+    };
+
+    Some(format!("{} {{{}}}", result_token(status), comment))
+}
+
+// level 40 5 0, level 0 2:30 1.5: moves, minutes with optional seconds, increment seconds
+fn parse_level (args: &[&str]) -> Option<Level> {
+    let [moves, base, increment] = args else { return None };
+
+    let base = match base.split_once(':') {
+        Some((minutes, seconds)) => minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?,
+        None => base.parse::<u64>().ok()? * 60,
+    };
+    let increment = increment.parse::<f64>().ok().filter(|secs| *secs >= 0.0)?;
+
+    Some(Level {
+        moves: moves.parse().ok()?,
+        base: Duration::from_secs(base),
+        increment: Duration::from_secs_f64(increment),
+    })
+}
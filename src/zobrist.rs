@@ -0,0 +1,93 @@
+use crate::bitboard::square;
+use crate::piece::{Color, Name, Position};
+
+struct Keys {
+    pieces: [[[u64; 64]; 6]; 2],
+    side: u64,
+    castling: [u64; 4],
+    passant: [u64; 8],
+}
+
+// generated at compile time from a fixed seed, so keys are stable across runs and builds
+static KEYS: Keys = generate();
+
+pub fn piece (name: Name, color: Color, pos: Position) -> u64 {
+    KEYS.pieces[color.index()][slot(name)][square(pos)]
+}
+
+// which piece gets which keys, spelled out rather than taken from Name::index so that
+// reordering Name leaves every hash, and every key stored from one, as it was
+const fn slot (name: Name) -> usize {
+    match name {
+        Name::Pawn => 0,
+        Name::King => 1,
+        Name::Queen => 2,
+        Name::Rook => 3,
+        Name::Knight => 4,
+        Name::Bishop => 5,
+    }
+}
+
+// xored in when black is to move
+pub fn side () -> u64 {
+    KEYS.side
+}
+
+// rights as bits in KQkq order, no rights at all hashes to 0
+pub fn castling (rights: u8) -> u64 {
+    (0..4)
+        .filter(|bit| rights & (1 << bit) != 0)
+        .fold(0, |key, bit| key ^ KEYS.castling[bit])
+}
+
+pub fn passant (target: Option<Position>) -> u64 {
+    target.map_or(0, |pos| KEYS.passant[pos.col])
+}
+
+const fn generate () -> Keys {
+    let mut keys = Keys {
+        pieces: [[[0; 64]; 6]; 2],
+        side: 0,
+        castling: [0; 4],
+        passant: [0; 8],
+    };
+    let mut state = 0x2545_F491_4F6C_DD1D;
+
+    let mut color = 0;
+    while color < 2 {
+        let mut name = 0;
+        while name < 6 {
+            let mut sq = 0;
+            while sq < 64 {
+                keys.pieces[color][name][sq] = splitmix(&mut state);
+                sq += 1;
+            }
+            name += 1;
+        }
+        color += 1;
+    }
+
+    keys.side = splitmix(&mut state);
+
+    let mut i = 0;
+    while i < 4 {
+        keys.castling[i] = splitmix(&mut state);
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < 8 {
+        keys.passant[i] = splitmix(&mut state);
+        i += 1;
+    }
+
+    keys
+}
+
+const fn splitmix (state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,144 @@
+#![allow(clippy::unused_unit)]
+
+mod common;
+
+use chess_rs::bitboard::{self, Bitboard};
+use chess_rs::board::{Board, PositionError};
+use chess_rs::fen::FenError;
+use chess_rs::game::{Game, GameStatus};
+use chess_rs::piece::pawn::PawnData;
+use chess_rs::piece::{Color, Name, Piece};
+
+use common::square;
+
+fn squares (names: &[&str]) -> Bitboard {
+    names.iter().fold(0, |bb, &name| bb | bitboard::bit(square(name)))
+}
+
+fn fen_board (fen: &str) -> Board {
+    Game::from_fen(fen).unwrap().board
+}
+
+// positions FEN can describe, so the game built from them is marked invalid
+fn invalid_fen (fen: &str, turn: Color, expected: &[PositionError]) -> () {
+    let game = Game::from_fen(fen).unwrap();
+
+    assert_eq!(game.board.validate(turn).unwrap_err(), expected, "{}", fen);
+    assert_eq!(game.status(), GameStatus::Invalid, "{}", fen);
+    assert!(game.legal_moves().is_empty());
+}
+
+#[test]
+fn missing_king () -> () {
+    invalid_fen("8/8/8/8/8/8/8/4K3 w - - 0 1", Color::White, &[PositionError::MissingKing(Color::Black)]);
+    invalid_fen("8/8/8/8/8/8/8/8 w - - 0 1", Color::White,
+                &[PositionError::MissingKing(Color::White), PositionError::MissingKing(Color::Black)]);
+}
+
+#[test]
+fn extra_kings () -> () {
+    invalid_fen("4k3/8/8/8/8/8/8/K3K3 w - - 0 1", Color::White, &[PositionError::ExtraKings(Color::White)]);
+}
+
+#[test]
+fn pawn_on_back_rank () -> () {
+    invalid_fen("P3k3/8/8/8/8/8/8/4K2p w - - 0 1", Color::White,
+                &[PositionError::PawnOnBackRank(square("a8")), PositionError::PawnOnBackRank(square("h1"))]);
+}
+
+#[test]
+fn opponent_in_check () -> () {
+    // white to move with the black king already attacked by the rook
+    invalid_fen("4k2R/8/8/8/8/8/8/4K3 w - - 0 1", Color::White, &[PositionError::OpponentInCheck(Color::Black)]);
+    // with black to move it is just check
+    assert_eq!(fen_board("4k2R/8/8/8/8/8/8/4K3 b - - 0 1").validate(Color::Black), Ok(()));
+}
+
+#[test]
+fn invalid_castling () -> () {
+    // FEN only grants rights to a king and rook on their starting squares
+    assert_eq!(Game::from_fen("4k3/8/8/8/R7/8/8/4K3 w Q - 0 1").err(), Some(FenError::InvalidCastling(String::from("Q"))));
+
+    // an unmoved rook anywhere else cannot be there
+    let mut board = fen_board("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    board.set(4, 0, Some(Piece::new(Name::Rook, square("a4"), Color::White)));
+    assert_eq!(board.validate(Color::White).unwrap_err(), [PositionError::InvalidCastling(square("a4"))]);
+
+    // nor an unmoved king off the e-file
+    let mut board = fen_board("4k3/8/8/8/8/8/8/8 w - - 0 1");
+    board.set(7, 3, Some(Piece::new(Name::King, square("d1"), Color::White)));
+    assert_eq!(board.validate(Color::White).unwrap_err(), [PositionError::InvalidCastling(square("d1"))]);
+}
+
+#[test]
+fn invalid_passant () -> () {
+    // FEN refuses a target no pawn can have made
+    assert_eq!(Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - c6 0 1").err(), Some(FenError::InvalidPassant(String::from("c6"))));
+
+    // a pawn handed a target with no pawn behind it that could have pushed there
+    let mut board = fen_board("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1");
+    let pawn = board.get_mut(3, 4).unwrap();
+    pawn.data.as_any_mut().downcast_mut::<PawnData>().unwrap().passant_target = Some(square("f6"));
+    assert_eq!(board.validate(Color::White).unwrap_err(), [PositionError::InvalidPassant(square("f6"))]);
+
+    // the same target is fine from the real double push, but not with black to move
+    let board = fen_board("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    assert_eq!(board.validate(Color::White), Ok(()));
+    assert_eq!(board.validate(Color::Black).unwrap_err(), [PositionError::InvalidPassant(square("d6"))]);
+}
+
+#[test]
+fn attackers_of_a_square () -> () {
+    // d5 is hit by the pawn, the knight and the rook, with the queen lined up behind the rook
+    let board = fen_board("4k3/8/8/3p4/2P5/4N3/3R4/K2Q4 w - - 0 1");
+    let d5 = square("d5");
+
+    assert_eq!(board.attackers_of(d5, Color::White), squares(&["c4", "e3", "d2"]));
+    assert_eq!(board.attackers_of(d5, Color::Black), 0);
+    assert!(board.is_attacked(d5, Color::White));
+    assert!(!board.is_attacked(d5, Color::Black));
+
+    // with the rook gone from occupied the queen sees through it, the rook itself still counts
+    let occupied = board.occupied() & !squares(&["d2"]);
+    assert_eq!(board.attackers_through(d5, Color::White, occupied), squares(&["c4", "e3", "d2", "d1"]));
+
+    // black's pawn covers the squares diagonally in front of it
+    assert_eq!(board.attackers_of(square("c4"), Color::Black), squares(&["d5"]));
+    assert_eq!(board.attackers_of(square("e4"), Color::Black), squares(&["d5"]));
+    assert_eq!(board.attackers_of(square("d4"), Color::Black), 0);
+}
+
+#[test]
+fn kings_attack_the_squares_around_them () -> () {
+    let game = Game::from_fen("8/8/8/3k4/8/3K4/8/8 w - - 0 1").unwrap();
+    let board = game.board.clone();
+
+    // the rank between them is covered by both
+    for name in ["c4", "d4", "e4"] {
+        assert!(board.is_attacked(square(name), Color::White), "{}", name);
+        assert!(board.is_attacked(square(name), Color::Black), "{}", name);
+    }
+    assert_eq!(board.attackers_of(square("d4"), Color::White), squares(&["d3"]));
+    assert_eq!(board.attackers_of(square("d4"), Color::Black), squares(&["d5"]));
+
+    // so neither king may step onto it
+    let targets = game.legal_targets(square("d3"));
+    assert_eq!(targets.len(), 5);
+    assert!(targets.iter().all(|pos| pos.row == 5 || pos.row == 6), "{:?}", targets);
+}
+
+#[test]
+fn attack_maps () -> () {
+    let board = Board::starting_position();
+
+    // all of the third rank and the second, and the first except the corners
+    assert_eq!(board.attack_map(Color::White), 0xFF << 40 | 0xFF << 48 | 0x7E << 56);
+    assert_eq!(board.attack_map(Color::Black), 0x7E | 0xFF << 8 | 0xFF << 16);
+
+    // sliders stop at the first piece in the way, a defended piece of their own included
+    let board = fen_board("4k3/8/8/8/1p6/8/8/R3K3 w - - 0 1");
+    let rook_and_king = squares(&["a2", "a3", "a4", "a5", "a6", "a7", "a8", "b1", "c1", "d1", "e1",
+                                   "d2", "e2", "f2", "f1"]);
+    assert_eq!(board.attack_map(Color::White), rook_and_king);
+    assert_eq!(board.attack_map(Color::Black), squares(&["a3", "c3", "d8", "f8", "d7", "e7", "f7"]));
+}
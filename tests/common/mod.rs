@@ -0,0 +1,8 @@
+// helpers shared by the integration tests, each test crate pulls them in with mod common
+
+use chess_rs::piece::Position;
+
+// a square from its algebraic name, panicking on a bad one as only tests call it
+pub fn square (name: &str) -> Position {
+    Position::from_algebraic(name).unwrap()
+}
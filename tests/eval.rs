@@ -0,0 +1,117 @@
+#![allow(clippy::unused_unit)]
+
+use std::env;
+use std::fs;
+
+use chess_rs::board::Board;
+use chess_rs::eval::{Evaluator, Score, Term, Weights, WeightsError};
+use chess_rs::game::Game;
+use chess_rs::piece::{Color, Name};
+
+const POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "4k3/pp4pp/8/3P4/8/8/PP3PPP/4K3 b - - 0 1",
+];
+
+fn board (fen: &str) -> Board {
+    Game::from_fen(fen).unwrap().board
+}
+
+fn term (evaluator: &Evaluator, board: &Board, color: Color, term: Term) -> Score {
+    evaluator.explain(board, color).terms.into_iter().find(|score| score.term == term).unwrap().score
+}
+
+#[test]
+fn explained_terms_add_up_to_the_evaluation () -> () {
+    let mut weights = Weights::default();
+    weights.mobility[Name::Pawn.index()] = Score::new(3, 7);
+    weights.king_attack = Score::new(-20, -5);
+
+    for evaluator in [Evaluator::default(), Evaluator::new(weights)] {
+        for fen in POSITIONS {
+            let board = board(fen);
+            for color in [Color::White, Color::Black] {
+                let explanation = evaluator.explain(&board, color);
+
+                assert_eq!(explanation.terms.iter().map(|term| term.term).collect::<Vec<_>>(), Term::ALL);
+                assert_eq!(explanation.terms.iter().map(|term| term.value).sum::<i32>(), explanation.total);
+                assert_eq!(explanation.total, evaluator.evaluate(&board, color), "{} {:?}", fen, color);
+            }
+            // whatever one side gains the other loses
+            assert_eq!(evaluator.evaluate(&board, Color::White), -evaluator.evaluate(&board, Color::Black));
+        }
+    }
+}
+
+#[test]
+fn pawn_and_king_mobility_are_counted () -> () {
+    // white's king has four squares and its pawn two pushes, black's king three squares
+    let board = board("7k/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    assert_eq!(term(&Evaluator::default(), &board, Color::White, Term::Mobility), Score::default());
+
+    let mut weights = Weights::default();
+    weights.mobility[Name::King.index()] = Score::new(10, 10);
+    assert_eq!(term(&Evaluator::new(weights), &board, Color::White, Term::Mobility), Score::new(10, 10));
+
+    let mut weights = Weights::default();
+    weights.mobility[Name::Pawn.index()] = Score::new(10, 20);
+    assert_eq!(term(&Evaluator::new(weights), &board, Color::White, Term::Mobility), Score::new(20, 40));
+}
+
+#[test]
+fn weights_round_trip () -> () {
+    let text = Weights::default().to_string();
+    assert_eq!(Weights::parse(&text).unwrap(), Weights::default());
+
+    let mut weights = Weights::default();
+    weights.material[Name::Knight.index()] = Score::new(305, 290);
+    weights.mobility[Name::King.index()] = Score::new(-1, 3);
+    weights.passed_pawn = Score::new(0, 40);
+    assert_eq!(Weights::parse(&weights.to_string()).unwrap(), weights);
+}
+
+#[test]
+fn weights_are_parsed () -> () {
+    let config = "# only what differs from the defaults\n\
+                  \n\
+                  material.queen = 950   # a single value sets both phases\n\
+                  mobility.pawn=2 6\n\
+                  king_attack = -12 -1\n";
+    let weights = Weights::parse(config).unwrap();
+
+    let mut expected = Weights::default();
+    expected.material[Name::Queen.index()] = Score::new(950, 950);
+    expected.mobility[Name::Pawn.index()] = Score::new(2, 6);
+    expected.king_attack = Score::new(-12, -1);
+    assert_eq!(weights, expected);
+}
+
+#[test]
+fn bad_weights_are_rejected () -> () {
+    let err = Weights::parse("pst = 100\nmaterial.dragon = 1\n").unwrap_err();
+    assert!(matches!(err, WeightsError::UnknownKey { line: 2, ref key } if key == "material.dragon"), "{:?}", err);
+
+    let err = Weights::parse("tempo = 10\n").unwrap_err();
+    assert!(matches!(err, WeightsError::UnknownKey { line: 1, .. }), "{:?}", err);
+
+    for value in ["", "ten", "1 2 3", "1.5"] {
+        let err = Weights::parse(&format!("\npst = {}\n", value)).unwrap_err();
+        assert!(matches!(err, WeightsError::InvalidValue { line: 2, .. }), "{:?}", err);
+    }
+
+    let err = Weights::parse("pst 100\n").unwrap_err();
+    assert!(matches!(err, WeightsError::Syntax { line: 1, ref text } if text == "pst 100"), "{:?}", err);
+}
+
+#[test]
+fn weights_from_file () -> () {
+    let path = env::temp_dir().join(format!("chess-rs-weights-{}.txt", std::process::id()));
+    fs::write(&path, "doubled_pawn = -30 -40\n").unwrap();
+    let weights = Weights::from_file(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(weights.unwrap().doubled_pawn, Score::new(-30, -40));
+    assert!(matches!(Weights::from_file(&path), Err(WeightsError::Io(_))));
+}
@@ -0,0 +1,93 @@
+#![allow(clippy::unused_unit)]
+
+use chess_rs::fen::{self, FenError, STARTING_FEN};
+use chess_rs::game::Game;
+
+fn error (fen: &str) -> FenError {
+    fen::parse(fen).err().unwrap()
+}
+
+#[test]
+fn positions_round_trip () -> () {
+    for fen in [
+        STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 3 17",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+    ] {
+        assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+    }
+}
+
+#[test]
+fn unusable_en_passant_target_is_dropped () -> () {
+    // black just played d5 but no white pawn stands next to it
+    let fen = "rnbqkbnr/ppp1pppp/8/3p4/8/8/PPPPPPPP/RNBQKBNR w KQkq d6 0 2";
+    let game = Game::from_fen(fen).unwrap();
+
+    assert_eq!(game.to_fen(), "rnbqkbnr/ppp1pppp/8/3p4/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 2");
+    let without = Game::from_fen(&game.to_fen()).unwrap();
+    assert_eq!(game.hash(), without.hash());
+}
+
+#[test]
+fn pinned_en_passant_target_is_dropped () -> () {
+    // bxc6 would leave the white king open to the rook along the fifth rank
+    let game = Game::from_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1").unwrap();
+    assert_eq!(game.to_fen(), "8/8/8/KPp4r/8/8/8/4k3 w - - 0 1");
+    assert_eq!(game.hash(), Game::from_fen(&game.to_fen()).unwrap().hash());
+
+    // the same when the double push is played, and after taking it back and playing it again
+    let mut game = Game::from_fen("8/2p5/8/KP5r/8/8/8/4k3 b - - 0 1").unwrap();
+    game.apply_san("c5").unwrap();
+    assert_eq!(game.to_fen(), "8/8/8/KPp4r/8/8/8/4k3 w - - 0 2");
+    assert_eq!(game.hash(), Game::from_fen(&game.to_fen()).unwrap().hash());
+    game.undo().unwrap();
+    game.redo().unwrap();
+    assert_eq!(game.to_fen(), "8/8/8/KPp4r/8/8/8/4k3 w - - 0 2");
+
+    // a diagonal pin on one of two capturers leaves the target to the other
+    let game = Game::from_fen("7k/8/8/1PpP4/8/8/8/4K3 w - c6 0 1").unwrap();
+    assert_eq!(game.to_fen(), "7k/8/8/1PpP4/8/8/8/4K3 w - c6 0 1");
+    let game = Game::from_fen("6bk/8/8/1PpP4/2K5/8/8/8 w - c6 0 1").unwrap();
+    assert_eq!(game.to_fen(), "6bk/8/8/1PpP4/2K5/8/8/8 w - c6 0 1");
+    let takers: Vec<String> = game.legal_moves().iter()
+        .filter(|mv| mv.to.to_string() == "c6")
+        .map(|mv| mv.from.to_string())
+        .collect();
+    assert_eq!(takers, ["b5"]);
+}
+
+#[test]
+fn en_passant_out_of_check_is_kept () -> () {
+    // taking the pawn that gives check is the one way out that involves it
+    let fen = "8/8/8/4k3/3Pp3/8/8/4K3 b - d3 0 1";
+    assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+
+    // but not when the rook's check along the rank is left standing
+    let game = Game::from_fen("8/8/8/R3k3/3Pp3/8/8/4K3 b - d3 0 1").unwrap();
+    assert_eq!(game.to_fen(), "8/8/8/R3k3/3Pp3/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn each_error_is_reported () -> () {
+    assert_eq!(error("8/8/8/8/8/8/8/8 w - - 0"), FenError::FieldCount(5));
+    assert_eq!(error("8/8/8/8/8/8/8 w - - 0 1"), FenError::RankCount(7));
+    assert_eq!(error("8/8/8/8/8/8/8/9 w - - 0 1"), FenError::InvalidPiece('9'));
+    assert_eq!(error("8/8/8/8/8/8/8/7 w - - 0 1"), FenError::RankLength(1));
+    assert_eq!(error("8/4k4/8/8/8/8/8/4K3 w - - 0 1"), FenError::RankLength(7));
+    assert_eq!(error("8/8/8/8/8/8/8/4X3 w - - 0 1"), FenError::InvalidPiece('X'));
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 x - - 0 1"), FenError::InvalidTurn(String::from("x")));
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w Z - 0 1"), FenError::InvalidCastling(String::from("Z")));
+    // the right needs its king and rook on their starting squares
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w K - 0 1"), FenError::InvalidCastling(String::from("K")));
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - e9 0 1"), FenError::InvalidPassant(String::from("e9")));
+    // no black pawn made a double push to e5
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - e6 0 1"), FenError::InvalidPassant(String::from("e6")));
+    // white to move, so the target has to be on the sixth rank
+    assert_eq!(error("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"), FenError::InvalidPassant(String::from("e3")));
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - -1 1"), FenError::InvalidHalfmove(String::from("-1")));
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - 0 0"), FenError::InvalidFullmove(String::from("0")));
+    assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - 0 one"), FenError::InvalidFullmove(String::from("one")));
+}
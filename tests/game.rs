@@ -0,0 +1,324 @@
+#![allow(clippy::unused_unit)]
+
+mod common;
+
+use chess_rs::game::{DrawReason, Game, GameStatus, MoveError};
+use chess_rs::lan::LanError;
+use chess_rs::piece::{Color, Name};
+use chess_rs::rules::is_insufficient_material;
+
+use common::square;
+
+#[test]
+fn default_game_is_invalid () -> () {
+    let game = Game::default();
+
+    assert!(game.board.validate(game.get_turn()).is_err());
+    assert_eq!(game.status(), GameStatus::Invalid);
+    assert_eq!(game.position_history(), [game.hash()]);
+}
+
+// everything undo and redo have to put back
+fn snapshot (game: &Game) -> (String, u64, u32, u32, GameStatus, usize, Vec<u64>) {
+    (game.to_fen(), game.hash(), game.get_halfmove_clock(), game.get_fullmove_number(),
+     game.status(), game.history().len(), game.position_history().to_vec())
+}
+
+fn undo_and_redo (sans: &[&str]) -> () {
+    let mut game = Game::new();
+    let mut snapshots = vec![snapshot(&game)];
+    for san in sans {
+        game.apply_san(san).unwrap();
+        snapshots.push(snapshot(&game));
+    }
+
+    for i in (0..sans.len()).rev() {
+        assert!(game.can_undo());
+        game.undo().unwrap();
+        assert_eq!(snapshot(&game), snapshots[i], "after undoing {}", sans[i]);
+    }
+    assert!(!game.can_undo());
+    assert!(game.undo().is_none());
+
+    for (i, san) in sans.iter().enumerate() {
+        assert!(game.can_redo());
+        let meta = game.redo().unwrap();
+        assert_eq!(meta.to_string(), *san);
+        assert_eq!(snapshot(&game), snapshots[i + 1], "after redoing {}", san);
+    }
+    assert!(!game.can_redo());
+}
+
+#[test]
+fn undo_and_redo_restore_everything () -> () {
+    // en passant, a capturing promotion and a quiet move for the clocks
+    undo_and_redo(&["e4", "d5", "exd5", "c5", "dxc6", "Nf6", "cxb7", "Nbd7", "bxa8=Q", "Nb6"]);
+    // castling and a check
+    undo_and_redo(&["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "O-O", "Bc5", "Bxf7+"]);
+    // a finished game comes back to life
+    undo_and_redo(&["f3", "e5", "g4", "Qh4#"]);
+}
+
+#[test]
+fn a_new_move_clears_the_redos () -> () {
+    let mut game = Game::new();
+    game.apply_san("e4").unwrap();
+    game.undo().unwrap();
+    assert!(game.can_redo());
+
+    game.apply_san("d4").unwrap();
+    assert!(!game.can_redo());
+    assert!(game.redo().is_none());
+    assert_eq!(game.history().len(), 1);
+}
+
+fn play (game: &mut Game, sans: &[&str]) -> () {
+    for san in sans {
+        game.apply_san(san).unwrap();
+    }
+}
+
+const KNIGHT_SHUFFLE: [&str; 4] = ["Nf3", "Nf6", "Ng1", "Ng8"];
+
+#[test]
+fn threefold_repetition_is_claimable () -> () {
+    let mut game = Game::new();
+    play(&mut game, &KNIGHT_SHUFFLE);
+    assert_eq!(game.claimable_draw(), None);
+
+    // the starting position comes round for the third time
+    play(&mut game, &KNIGHT_SHUFFLE);
+    assert_eq!(game.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+    assert_eq!(game.status(), GameStatus::Ongoing);
+
+    // until claimed the game goes on, and moving away takes the claim with it
+    game.apply_san("e4").unwrap();
+    assert_eq!(game.claimable_draw(), None);
+    game.undo().unwrap();
+
+    assert_eq!(game.claim_draw(), Ok(GameStatus::Draw(DrawReason::ThreefoldRepetition)));
+    assert_eq!(game.status(), GameStatus::Draw(DrawReason::ThreefoldRepetition));
+    assert_eq!(game.claim_draw(), Err(MoveError::GameOver));
+}
+
+#[test]
+fn fivefold_repetition_ends_the_game () -> () {
+    let mut game = Game::new();
+    for _ in 0..3 {
+        play(&mut game, &KNIGHT_SHUFFLE);
+    }
+    assert_eq!(game.status(), GameStatus::Ongoing);
+
+    play(&mut game, &KNIGHT_SHUFFLE);
+    assert_eq!(game.status(), GameStatus::Draw(DrawReason::FivefoldRepetition));
+    assert_eq!(game.claimable_draw(), None);
+    assert_eq!(game.apply_uci("e2e4").unwrap_err(), LanError::Move(MoveError::GameOver));
+}
+
+#[test]
+fn fifty_move_rule_is_claimable () -> () {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+    assert_eq!(game.claimable_draw(), None);
+
+    game.apply_san("Ra2").unwrap();
+    assert_eq!(game.get_halfmove_clock(), 100);
+    assert_eq!(game.status(), GameStatus::Ongoing);
+    assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+    assert_eq!(game.claim_draw(), Ok(GameStatus::Draw(DrawReason::FiftyMoveRule)));
+}
+
+#[test]
+fn seventy_five_move_rule_ends_the_game () -> () {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 100").unwrap();
+    assert_eq!(game.status(), GameStatus::Ongoing);
+
+    game.apply_san("Ra2").unwrap();
+    assert_eq!(game.status(), GameStatus::Draw(DrawReason::SeventyFiveMoveRule));
+
+    // a capture or a pawn move in between starts the count again
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 100").unwrap();
+    game.apply_san("e4").unwrap();
+    assert_eq!(game.get_halfmove_clock(), 0);
+    assert_eq!(game.status(), GameStatus::Ongoing);
+}
+
+#[test]
+fn no_draw_to_claim () -> () {
+    let mut game = Game::new();
+
+    assert_eq!(game.claimable_draw(), None);
+    assert_eq!(game.claim_draw(), Err(MoveError::NoDrawToClaim));
+    assert_eq!(game.status(), GameStatus::Ongoing);
+}
+
+#[test]
+fn insufficient_material () -> () {
+    let draws = [
+        "8/8/8/4k3/8/8/8/4K3 w - - 0 1",
+        "8/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+        "8/8/8/4k3/8/8/8/1N2K3 w - - 0 1",
+        // bishops on both sides, all on dark squares
+        "5b2/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+        "5b2/8/8/4k3/8/B7/8/2B1K3 w - - 0 1",
+    ];
+    for fen in draws {
+        let game = Game::from_fen(fen).unwrap();
+        assert!(is_insufficient_material(&game.board), "{}", fen);
+        assert_eq!(game.status(), GameStatus::Draw(DrawReason::InsufficientMaterial), "{}", fen);
+    }
+
+    let playable = [
+        // two knights can mate with help, so it is not a dead position
+        "8/8/8/4k3/8/8/8/1N2KN2 w - - 0 1",
+        "2b5/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+        "8/8/8/4k3/8/8/8/1NB1K3 w - - 0 1",
+        "8/8/8/4k3/8/8/4P3/4K3 w - - 0 1",
+        "8/8/8/4k3/8/8/8/R3K3 w - - 0 1",
+    ];
+    for fen in playable {
+        let game = Game::from_fen(fen).unwrap();
+        assert!(!is_insufficient_material(&game.board), "{}", fen);
+        assert_eq!(game.status(), GameStatus::Ongoing, "{}", fen);
+    }
+
+    // taking the last piece that could mate ends the game there and then
+    let mut game = Game::from_fen("8/8/8/4k3/3r4/8/8/3QK3 w - - 0 1").unwrap();
+    game.apply_san("Qxd4+").unwrap();
+    game.apply_san("Kxd4").unwrap();
+    assert_eq!(game.status(), GameStatus::Draw(DrawReason::InsufficientMaterial));
+}
+
+fn attempt (game: &mut Game, from: &str, to: &str) -> Result<(), MoveError> {
+    let before = (game.to_fen(), game.history().len());
+    let result = game.apply_move(square(from), square(to)).map(|_| ());
+    // a rejected move leaves the game as it was
+    if result.is_err() {
+        assert_eq!((game.to_fen(), game.history().len()), before);
+    }
+    result
+}
+
+#[test]
+fn no_piece_at_source () -> () {
+    let mut game = Game::new();
+    assert_eq!(attempt(&mut game, "e4", "e5"), Err(MoveError::NoPieceAtSource));
+}
+
+#[test]
+fn not_your_turn () -> () {
+    let mut game = Game::new();
+    assert_eq!(attempt(&mut game, "e7", "e5"), Err(MoveError::NotYourTurn));
+
+    game.apply_san("e4").unwrap();
+    assert_eq!(attempt(&mut game, "d2", "d4"), Err(MoveError::NotYourTurn));
+}
+
+#[test]
+fn illegal_target () -> () {
+    let mut game = Game::new();
+    assert_eq!(attempt(&mut game, "e2", "e5"), Err(MoveError::IllegalMove));
+    assert_eq!(attempt(&mut game, "g1", "g3"), Err(MoveError::IllegalMove));
+    // onto a piece of its own
+    assert_eq!(attempt(&mut game, "d1", "d2"), Err(MoveError::IllegalMove));
+}
+
+#[test]
+fn leaving_the_king_in_check () -> () {
+    // the e2 knight is pinned by the rook on e8
+    let mut game = Game::from_fen("4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+    assert_eq!(attempt(&mut game, "e2", "c3"), Err(MoveError::IllegalMove));
+
+    // and in check, a move that does nothing about it is no move at all
+    let mut game = Game::from_fen("4r1k1/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    assert_eq!(game.status(), GameStatus::Check);
+    assert_eq!(attempt(&mut game, "a1", "a2"), Err(MoveError::IllegalMove));
+    // nor can the king step along the checking line
+    assert_eq!(attempt(&mut game, "e1", "e2"), Err(MoveError::IllegalMove));
+}
+
+#[test]
+fn promotions () -> () {
+    let fen = "8/4P1k1/8/8/8/8/8/4K3 w - - 0 1";
+    let mut game = Game::from_fen(fen).unwrap();
+
+    assert_eq!(attempt(&mut game, "e7", "e8"), Err(MoveError::PromotionRequired));
+    for name in [Name::King, Name::Pawn] {
+        let result = game.apply_move_with_promotion(square("e7"), square("e8"), name);
+        assert_eq!(result.unwrap_err(), MoveError::InvalidPromotion(name));
+    }
+    // a piece to promote to on a move that is no promotion
+    let result = game.apply_move_with_promotion(square("e1"), square("d1"), Name::Queen);
+    assert_eq!(result.unwrap_err(), MoveError::InvalidPromotion(Name::Queen));
+    assert_eq!(game.to_fen(), fen);
+
+    let meta = game.apply_move_with_promotion(square("e7"), square("e8"), Name::Knight).unwrap();
+    assert_eq!(meta.promotion, Some(Name::Knight));
+    assert_eq!(game.board.get(0, 4).map(|p| (p.name, p.color)), Some((Name::Knight, Color::White)));
+}
+
+#[test]
+fn game_over_and_invalid_positions () -> () {
+    let mut game = Game::new();
+    game.resign(Color::White).unwrap();
+    assert_eq!(attempt(&mut game, "e2", "e4"), Err(MoveError::GameOver));
+
+    let mut game = Game::default();
+    assert_eq!(attempt(&mut game, "e2", "e4"), Err(MoveError::InvalidPosition));
+}
+
+#[test]
+fn status_follows_the_moves () -> () {
+    let mut game = Game::new();
+    assert_eq!(game.status(), GameStatus::Ongoing);
+
+    play(&mut game, &["e4", "f5", "Qh5+"]);
+    assert_eq!(game.status(), GameStatus::Check);
+    assert!(!game.status().is_over());
+
+    play(&mut game, &["g6"]);
+    assert_eq!(game.status(), GameStatus::Ongoing);
+}
+
+#[test]
+fn no_moves_after_checkmate () -> () {
+    let mut game = Game::new();
+    play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+
+    assert_eq!(game.status(), GameStatus::Checkmate(Color::Black));
+    assert!(game.status().is_over());
+    assert!(game.legal_moves().is_empty());
+    assert_eq!(attempt(&mut game, "e2", "e4"), Err(MoveError::GameOver));
+    assert_eq!(game.resign(Color::White), Err(MoveError::GameOver));
+}
+
+#[test]
+fn no_moves_after_stalemate () -> () {
+    // black's king has nowhere to go once the queen covers b6
+    let mut game = Game::from_fen("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1").unwrap();
+    game.apply_san("Qc7").unwrap();
+
+    assert_eq!(game.status(), GameStatus::Stalemate);
+    assert_eq!(attempt(&mut game, "a8", "b8"), Err(MoveError::GameOver));
+
+    // positions set up as already over start out that way
+    assert_eq!(Game::from_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap().status(), GameStatus::Stalemate);
+    assert_eq!(Game::from_fen("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1").unwrap().status(), GameStatus::Checkmate(Color::White));
+}
+
+#[test]
+fn no_moves_after_resignation_or_time () -> () {
+    let mut game = Game::new();
+    game.apply_san("e4").unwrap();
+    assert_eq!(game.resign(Color::Black), Ok(GameStatus::Resigned(Color::White)));
+    assert_eq!(attempt(&mut game, "e7", "e5"), Err(MoveError::GameOver));
+    assert_eq!(game.agree_draw(), Err(MoveError::GameOver));
+    assert_eq!(game.status(), GameStatus::Resigned(Color::White));
+
+    let mut game = Game::new();
+    assert_eq!(game.time_forfeit(Color::White), Ok(GameStatus::TimeForfeit(Color::Black)));
+    assert_eq!(attempt(&mut game, "e2", "e4"), Err(MoveError::GameOver));
+
+    let mut game = Game::new();
+    assert_eq!(game.agree_draw(), Ok(GameStatus::Draw(DrawReason::Agreement)));
+    assert_eq!(attempt(&mut game, "e2", "e4"), Err(MoveError::GameOver));
+}
@@ -0,0 +1,74 @@
+#![allow(clippy::unused_unit)]
+
+mod common;
+
+use chess_rs::game::{Game, MoveError};
+use chess_rs::lan::{self, LanError};
+use chess_rs::piece::Name;
+
+use common::square;
+
+#[test]
+fn moves_parse_and_write_back () -> () {
+    assert_eq!(lan::parse("e2e4"), Ok((square("e2"), square("e4"), None)));
+    assert_eq!(lan::parse("a7b8n"), Ok((square("a7"), square("b8"), Some(Name::Knight))));
+
+    for text in ["e2e4", "g1f3", "e7e8q", "b2a1r", "h7h8b", "a7a8n"] {
+        let (from, to, promotion) = lan::parse(text).unwrap();
+        assert_eq!(lan::write(from, to, promotion), text);
+    }
+}
+
+#[test]
+fn malformed_input () -> () {
+    // wrong length, off the board, uppercase or unknown promotion letters, stray characters
+    for text in ["", "e2", "e2e", "e2e4q5", "e2-e4", "i2i4", "e0e1", "e9e8", "e7e8Q", "e7e8x", "e7e8+", "é2e4", " e2e4"] {
+        assert_eq!(lan::parse(text), Err(LanError::Malformed(text.to_string())), "{:?}", text);
+    }
+
+    let mut game = Game::new();
+    assert_eq!(game.apply_uci("e2e9").unwrap_err(), LanError::Malformed(String::from("e2e9")));
+    assert!(game.history().is_empty());
+}
+
+#[test]
+fn promotions_need_their_suffix () -> () {
+    let mut game = Game::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+    assert_eq!(game.apply_uci("a7a8").unwrap_err(), LanError::Move(MoveError::PromotionRequired));
+    assert_eq!(game.apply_uci("a7a8k").unwrap_err(), LanError::Move(MoveError::InvalidPromotion(Name::King)));
+    // and only promotions may have one
+    assert_eq!(game.apply_uci("e1e2q").unwrap_err(), LanError::Move(MoveError::InvalidPromotion(Name::Queen)));
+
+    let meta = game.apply_uci("a7a8q").unwrap();
+    assert_eq!(meta.promotion, Some(Name::Queen));
+    assert_eq!(meta.to_uci(), "a7a8q");
+    assert_eq!(game.to_fen(), "Q3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn castling_is_the_king_move () -> () {
+    let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+    let meta = game.apply_uci("e1g1").unwrap();
+    assert_eq!(meta.to_uci(), "e1g1");
+    assert_eq!(meta.to_string(), "O-O");
+    let meta = game.apply_uci("e8c8").unwrap();
+    assert_eq!(meta.to_string(), "O-O-O");
+    assert_eq!(game.to_fen(), "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+
+    // the rook's own square is no way of writing it
+    let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    assert_eq!(game.apply_uci("e1h1").unwrap_err(), LanError::Move(MoveError::IllegalMove));
+}
+
+#[test]
+fn legal_moves_are_written_as_uci () -> () {
+    let game = Game::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
+
+    for mv in game.legal_moves() {
+        let mut copy = game.clone();
+        let meta = copy.apply_uci(&mv.to_string()).unwrap();
+        assert_eq!(meta.to_uci(), mv.to_string());
+    }
+}
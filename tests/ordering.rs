@@ -0,0 +1,111 @@
+#![allow(clippy::unused_unit)]
+
+use chess_rs::bench;
+use chess_rs::board::Board;
+use chess_rs::eval::value;
+use chess_rs::game::Game;
+use chess_rs::ordering::{is_quiet, see, History, MovePicker};
+use chess_rs::piece::{Color, Move, Name};
+use chess_rs::rules::legal_moves;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn position (fen: &str) -> (Board, Vec<Move>) {
+    let game = Game::from_fen(fen).unwrap();
+    let mut board = game.board.clone();
+    let moves = legal_moves(&mut board, game.get_turn());
+    (board, moves)
+}
+
+fn find (moves: &[Move], uci: &str) -> Move {
+    *moves.iter().find(|mv| mv.to_string() == uci).unwrap()
+}
+
+fn see_of (fen: &str, uci: &str) -> i32 {
+    let (board, moves) = position(fen);
+    see(&board, &find(&moves, uci))
+}
+
+#[test]
+fn see_on_fixed_positions () -> () {
+    // the knight on d5 is not defended
+    assert_eq!(see_of("4k3/8/8/3n4/4P3/8/8/4K3 w - - 0 1", "e4d5"), value(Name::Knight));
+    // the queen takes a pawn and is taken back by another
+    assert_eq!(see_of("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", "d1d5"), value(Name::Pawn) - value(Name::Queen));
+    // knight for knight
+    assert_eq!(see_of("4k3/8/2p5/3n4/8/4N3/8/4K3 w - - 0 1", "e3d5"), 0);
+    // winning the pawn back with the second rook does not make up for the first
+    assert_eq!(see_of("4k3/8/2p5/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), value(Name::Pawn) - value(Name::Rook));
+}
+
+#[test]
+fn see_counts_pieces_behind_the_first_attacker () -> () {
+    // the rook on d1 joins once the one on d2 has gone to d5; without it the exchange
+    // would lose a rook for a pawn
+    assert_eq!(see_of("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"), value(Name::Pawn));
+    // a queen behind the bishop recaptures through the diagonal
+    assert_eq!(see_of("4k3/6q1/8/8/3p4/8/1B6/Q3K3 w - - 0 1", "b2d4"), value(Name::Pawn));
+}
+
+// all of a picker's moves, with the history scoring the quiet ones
+fn picked (board: &Board, moves: &[Move], hash_move: Option<Move>, killers: [Option<Move>; 2], history: &History) -> Vec<Move> {
+    let color = board.get(moves[0].from.row as i8, moves[0].from.col as i8).unwrap().color;
+    let mut picker = MovePicker::new(moves.to_vec(), hash_move, killers, color);
+
+    let mut out = Vec::new();
+    while let Some(mv) = picker.next(board, history) {
+        out.push(mv);
+    }
+    out
+}
+
+fn assert_each_once (picked: &[Move], moves: &[Move]) -> () {
+    assert_eq!(picked.len(), moves.len());
+    for mv in moves {
+        assert_eq!(picked.iter().filter(|&m| m == mv).count(), 1, "{}", mv);
+    }
+}
+
+#[test]
+fn picker_yields_every_move_once () -> () {
+    let (board, moves) = position(KIWIPETE);
+    let quiet: Vec<Move> = moves.iter().copied().filter(is_quiet).collect();
+    let capture = find(&moves, "e2a6");
+
+    let mut history = History::default();
+    history.add(Color::White, &quiet[5], 10);
+
+    // no hints at all
+    assert_each_once(&picked(&board, &moves, None, [None, None], &history), &moves);
+
+    // a quiet hash move that is also a killer, and a capture as the other killer
+    let out = picked(&board, &moves, Some(quiet[0]), [Some(quiet[0]), Some(capture)], &history);
+    assert_each_once(&out, &moves);
+    assert_eq!(out[0], quiet[0]);
+
+    // a capture as the hash move, two quiet killers and a history move
+    let out = picked(&board, &moves, Some(capture), [Some(quiet[1]), Some(quiet[2])], &history);
+    assert_each_once(&out, &moves);
+    assert_eq!(out[0], capture);
+    let first_quiet = out.iter().position(is_quiet).unwrap();
+    assert_eq!(out[first_quiet..first_quiet + 3], [quiet[1], quiet[2], quiet[5]]);
+}
+
+#[test]
+fn picker_ignores_moves_that_are_not_there () -> () {
+    let (board, moves) = position(KIWIPETE);
+    // moves from another position entirely
+    let (_, elsewhere) = position("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1");
+    let stranger = find(&elsewhere, "a1a8");
+
+    let out = picked(&board, &moves, Some(stranger), [Some(stranger), None], &History::default());
+    assert_each_once(&out, &moves);
+}
+
+#[test]
+fn bench_node_count () -> () {
+    // the suite came to 2,003,063 nodes at this depth before moves were ordered; the count
+    // is exact so that any change to the search or its ordering has to come with a new one
+    let (nodes, _) = bench::run(3, |_, result| assert!(result.best_move.is_some()));
+    assert_eq!(nodes, 47_178);
+}
@@ -0,0 +1,84 @@
+#![allow(clippy::unused_unit)]
+
+use chess_rs::fen::STARTING_FEN;
+use chess_rs::game::Game;
+use chess_rs::piece::MoveKind;
+
+// reference positions and node counts from the chessprogramming wiki
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+fn check (fen: &str, expected: &[u64]) -> () {
+    let game = Game::from_fen(fen).unwrap();
+
+    for (depth, &nodes) in expected.iter().enumerate() {
+        let depth = depth as u32 + 1;
+        assert_eq!(game.perft(depth), nodes, "{} at depth {}", fen, depth);
+    }
+}
+
+#[test]
+fn initial_position () -> () {
+    check(STARTING_FEN, &[20, 400, 8902]);
+}
+
+#[test]
+fn kiwipete () -> () {
+    check(KIWIPETE, &[48, 2039]);
+}
+
+#[test]
+fn position_3 () -> () {
+    check(POSITION_3, &[14, 191, 2812]);
+}
+
+#[test]
+fn position_4 () -> () {
+    check(POSITION_4, &[6, 264, 9467]);
+}
+
+#[test]
+fn position_5 () -> () {
+    check(POSITION_5, &[44, 1486]);
+}
+
+#[test]
+fn position_6 () -> () {
+    check(POSITION_6, &[46, 2079]);
+}
+
+#[test]
+fn divide_sums_to_perft () -> () {
+    let game = Game::from_fen(KIWIPETE).unwrap();
+    let split = game.divide(2);
+
+    assert_eq!(split.len(), 48);
+    assert_eq!(split.iter().map(|(_, nodes)| nodes).sum::<u64>(), game.perft(2));
+    assert!(split.iter().any(|(uci, _)| uci == "e1g1"));
+}
+
+#[test]
+fn move_kinds () -> () {
+    let count = |fen: &str, kind: MoveKind| {
+        Game::from_fen(fen).unwrap().legal_moves().iter().filter(|mv| mv.kind == kind).count()
+    };
+
+    assert_eq!(count(KIWIPETE, MoveKind::Capture), 8);
+    assert_eq!(count(KIWIPETE, MoveKind::Castle), 2);
+    assert_eq!(count(STARTING_FEN, MoveKind::DoublePush), 8);
+    assert_eq!(count("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3", MoveKind::EnPassant), 1);
+}
+
+#[test]
+#[ignore = "slow in debug builds, run with --release -- --ignored"]
+fn deep () -> () {
+    check(STARTING_FEN, &[20, 400, 8902, 197281]);
+    check(KIWIPETE, &[48, 2039, 97862]);
+    check(POSITION_3, &[14, 191, 2812, 43238, 674624]);
+    check(POSITION_4, &[6, 264, 9467, 422333]);
+    check(POSITION_5, &[44, 1486, 62379]);
+    check(POSITION_6, &[46, 2079, 89890]);
+}
@@ -0,0 +1,130 @@
+#![allow(clippy::unused_unit)]
+
+use chess_rs::game::{Game, GameStatus};
+use chess_rs::pgn::{PgnError, PgnGame, PgnReader};
+
+const ANNOTATED: &str = r#"[Event "Casual"]
+[Site "?"]
+[Date "2024.01.02"]
+[Round "1"]
+[White "Anderssen, \"The\" Adolf"]
+[Black "Kieseritzky"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. f4 $1 {The King's Gambit} exf4 (2... d5 {countergambit} 3. exd5)
+3. Bc4 Qh4+ ?! 4. Kf1 1-0
+"#;
+
+#[test]
+fn tags_comments_nags_and_variations () -> () {
+    let pgn = PgnGame::parse(ANNOTATED).unwrap();
+
+    assert_eq!(pgn.tag("White"), Some("Anderssen, \"The\" Adolf"));
+    assert_eq!(pgn.tag("Result"), Some("1-0"));
+    assert_eq!(pgn.comments, ["Opening comment"]);
+    assert_eq!(pgn.moves.len(), 7);
+    assert_eq!(pgn.result, "1-0");
+
+    let f4 = &pgn.moves[2];
+    assert_eq!(f4.san, "f4");
+    assert_eq!(f4.nags, [1]);
+    assert_eq!(f4.comments, ["The King's Gambit"]);
+
+    let exf4 = &pgn.moves[3];
+    assert_eq!(exf4.variations.len(), 1);
+    let sans: Vec<&str> = exf4.variations[0].moves.iter().map(|mv| mv.san.as_str()).collect();
+    assert_eq!(sans, ["d5", "exd5"]);
+    assert_eq!(exf4.variations[0].moves[0].comments, ["countergambit"]);
+
+    assert_eq!(pgn.moves[5].nags, [6]);
+}
+
+#[test]
+fn written_games_read_back_the_same () -> () {
+    let pgn = PgnGame::parse(ANNOTATED).unwrap();
+    let written = pgn.to_string();
+
+    assert_eq!(PgnGame::parse(&written).unwrap(), pgn);
+    // the comment interrupts the line, so black's move gets its number again
+    assert!(written.contains("2. f4 $1 {The King's Gambit} 2... exf4"), "{}", written);
+    assert!(written.contains("(2... d5 {countergambit} 3. exd5) 3. Bc4 Qh4+ $6"), "{}", written);
+}
+
+#[test]
+fn result_tag_does_not_end_the_game () -> () {
+    let pgn = PgnGame::parse(ANNOTATED).unwrap();
+    let game = pgn.to_game().unwrap();
+
+    // white won by resignation, which is nothing the moves themselves show
+    assert_eq!(game.status(), GameStatus::Ongoing);
+    assert_eq!(pgn.result, "1-0");
+}
+
+#[test]
+fn games_from_a_game () -> () {
+    let mut game = Game::new();
+    for san in ["f3", "e5", "g4", "Qh4#"] {
+        game.apply_san(san).unwrap();
+    }
+
+    let pgn = game.to_pgn(&[("Event", "Fool's mate")]);
+    assert!(pgn.contains("[Result \"0-1\"]") && pgn.trim_end().ends_with("2. g4 Qh4# 0-1"), "{}", pgn);
+
+    let back = Game::from_pgn(&pgn).unwrap();
+    assert_eq!(back.to_fen(), game.to_fen());
+    assert_eq!(back.status(), GameStatus::Checkmate(chess_rs::piece::Color::Black));
+}
+
+#[test]
+fn setup_tags_only_for_a_different_start () -> () {
+    // from the standard start, or a game with no recorded start at all, there is nothing to set up
+    for game in [Game::new(), Game::default()] {
+        let pgn = game.to_pgn(&[]);
+        assert!(!pgn.contains("[SetUp ") && !pgn.contains("[FEN "), "{}", pgn);
+    }
+
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    let mut game = Game::from_fen(fen).unwrap();
+    game.apply_san("e4").unwrap();
+    let pgn = game.to_pgn(&[]);
+    assert!(pgn.contains("[SetUp \"1\"]") && pgn.contains(&format!("[FEN \"{}\"]", fen)), "{}", pgn);
+    assert_eq!(Game::from_pgn(&pgn).unwrap().to_fen(), game.to_fen());
+}
+
+#[test]
+fn reader_streams_games_one_by_one () -> () {
+    let input = "[Event \"One\"]\n\n1. e4 e5 1-0\n\n[Event \"Two\"]\n\n1. d4 d5 2. c4 0-1\n\n[Event \"Three\"]\n\n*\n";
+    let games: Vec<PgnGame> = PgnReader::new(input.as_bytes()).collect::<Result<_, _>>().unwrap();
+
+    let events: Vec<Option<&str>> = games.iter().map(|game| game.tag("Event")).collect();
+    assert_eq!(events, [Some("One"), Some("Two"), Some("Three")]);
+    assert_eq!(games.iter().map(|game| game.moves.len()).collect::<Vec<_>>(), [2, 3, 0]);
+    assert_eq!(games.iter().map(|game| game.result.as_str()).collect::<Vec<_>>(), ["1-0", "0-1", "*"]);
+}
+
+#[test]
+fn reader_carries_on_after_a_broken_game () -> () {
+    let input = "[Event \"One\"]\n\n1. e4 @ e5 2. Nf3 1-0\n\n[Event \"Two\"\n[Site \"?\"]\n\n1. d4 *\n\n\
+                 [Event \"Three\"]\n[Site \"?\"]\n\n1. c4 *\n";
+    let games: Vec<Result<PgnGame, PgnError>> = PgnReader::new(input.as_bytes()).collect();
+
+    assert_eq!(games.len(), 3, "{:?}", games);
+    assert!(matches!(&games[0], Err(PgnError::Syntax(msg)) if msg.contains('@')), "{:?}", games[0]);
+    assert!(matches!(&games[1], Err(PgnError::Syntax(_))), "{:?}", games[1]);
+    let three = games[2].as_ref().unwrap();
+    assert_eq!(three.tag("Event"), Some("Three"));
+    assert_eq!(three.moves[0].san, "c4");
+}
+
+#[test]
+fn bad_moves_and_fens_are_errors () -> () {
+    let illegal = PgnGame::parse("1. e4 e5 2. Ke3 *").unwrap().to_game().err();
+    assert!(matches!(illegal, Some(PgnError::Move { ply: 2, ref san, .. }) if san == "Ke3"), "{:?}", illegal);
+
+    let fen = PgnGame::parse("[FEN \"not a fen\"]\n\n*").unwrap().to_game().err();
+    assert!(matches!(fen, Some(PgnError::Fen(_))), "{:?}", fen);
+
+    assert!(matches!(PgnGame::parse(""), Err(PgnError::Syntax(_))));
+    assert!(matches!(PgnGame::parse("1. e4 {never closed"), Err(PgnError::Syntax(_))));
+    assert!(matches!(PgnGame::parse("1. e4 (1. d4"), Err(PgnError::Syntax(_))));
+}
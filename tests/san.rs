@@ -0,0 +1,65 @@
+#![allow(clippy::unused_unit)]
+
+mod common;
+
+use chess_rs::game::Game;
+use chess_rs::piece::{Name, Position};
+use chess_rs::san::SanError;
+
+use common::square;
+
+// white pawns on c4 and e4 can both take on d5, the one on d4 can only push
+const CENTER: &str = "4k3/8/8/3p4/2PPP3/8/8/4K3 w - - 0 1";
+
+fn apply (fen: &str, san: &str) -> Result<(Position, Position), SanError> {
+    let mut game = Game::from_fen(fen).unwrap();
+    game.apply_san(san).map(|meta| (meta.from, meta.to))
+}
+
+#[test]
+fn pawn_moves_need_their_file_and_capture_marked () -> () {
+    assert_eq!(apply(CENTER, "cxd5"), Ok((square("c4"), square("d5"))));
+    assert_eq!(apply(CENTER, "exd5"), Ok((square("e4"), square("d5"))));
+    assert_eq!(apply(CENTER, "e5"), Ok((square("e4"), square("e5"))));
+
+    // d5 would be a push from d4, which is blocked; it is not cxd5
+    assert_eq!(apply(CENTER, "d5"), Err(SanError::NoSuchMove(String::from("d5"))));
+    assert_eq!(apply(CENTER, "xd5"), Err(SanError::Malformed(String::from("xd5"))));
+    assert_eq!(apply(CENTER, "cd5"), Err(SanError::Malformed(String::from("cd5"))));
+    assert_eq!(apply(CENTER, "dxd5"), Err(SanError::NoSuchMove(String::from("dxd5"))));
+    assert_eq!(apply(CENTER, "exe5"), Err(SanError::NoSuchMove(String::from("exe5"))));
+}
+
+#[test]
+fn capture_mark_must_match_the_move () -> () {
+    let fen = "4k3/8/8/3p4/8/8/8/3RK3 w - - 0 1";
+
+    assert_eq!(apply(fen, "Rxd5"), Ok((square("d1"), square("d5"))));
+    assert_eq!(apply(fen, "Rd5"), Err(SanError::NoSuchMove(String::from("Rd5"))));
+    assert_eq!(apply(fen, "Rxd4"), Err(SanError::NoSuchMove(String::from("Rxd4"))));
+    assert_eq!(apply(fen, "Rd4"), Ok((square("d1"), square("d4"))));
+    assert_eq!(apply(fen, "Rd1-d4"), Err(SanError::Malformed(String::from("Rd1-d4"))));
+    assert_eq!(apply(fen, "Rdx1d4"), Err(SanError::Malformed(String::from("Rdx1d4"))));
+}
+
+#[test]
+fn en_passant_is_a_capture () -> () {
+    let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+
+    assert_eq!(apply(fen, "exd6"), Ok((square("e5"), square("d6"))));
+    assert_eq!(apply(fen, "d6"), Err(SanError::NoSuchMove(String::from("d6"))));
+}
+
+#[test]
+fn disambiguation_and_promotion () -> () {
+    let fen = "4k3/P7/8/8/8/8/4K3/R6R w - - 0 1";
+
+    assert_eq!(apply(fen, "Rad1"), Ok((square("a1"), square("d1"))));
+    assert_eq!(apply(fen, "Rd1"), Err(SanError::Ambiguous(String::from("Rd1"))));
+    assert_eq!(apply("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "O-O"), Ok((square("e1"), square("g1"))));
+
+    let mut game = Game::from_fen(fen).unwrap();
+    let meta = game.apply_san("a8=Q+").unwrap();
+    assert_eq!(meta.promotion, Some(Name::Queen));
+    assert_eq!(apply(fen, "Ka8=Q"), Err(SanError::Malformed(String::from("Ka8=Q"))));
+}
@@ -0,0 +1,119 @@
+#![allow(clippy::unused_unit)]
+
+use chess_rs::game::{Game, GameStatus};
+use chess_rs::search::{Limits, Search};
+
+use std::time::Duration;
+
+fn depth (depth: u32) -> Limits {
+    Limits { depth: Some(depth), ..Limits::default() }
+}
+
+#[test]
+fn empty_board_has_no_move () -> () {
+    let result = Game::default().search(depth(3));
+
+    assert_eq!(result.best_move, None);
+    assert!(result.pv.is_empty());
+}
+
+#[test]
+fn invalid_position_has_no_move () -> () {
+    // black is in check with white to move, the rook could take the king
+    let game = Game::from_fen("4k2R/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(game.status(), GameStatus::Invalid);
+
+    let result = game.search(depth(3));
+    assert_eq!(result.best_move, None);
+    assert!(result.pv.is_empty());
+}
+
+#[test]
+fn start_position_has_a_move () -> () {
+    let mut game = Game::new();
+    let result = game.search(depth(3));
+
+    assert_eq!(result.depth, 3);
+    let mv = result.best_move.unwrap();
+    assert_eq!(result.pv.first(), Some(&mv));
+    assert!(game.apply(mv).is_ok());
+}
+
+#[test]
+fn cut_off_iterations_are_not_reported () -> () {
+    let mut game = Game::new();
+    let limits = Limits { nodes: Some(5), ..Limits::default() };
+    let mut reported = Vec::new();
+    let result = Search::new(limits).run_with(&game, |result| reported.push(result.depth));
+
+    // depth 1 alone needs more nodes than that, so nothing finished
+    assert!(reported.is_empty(), "{:?}", reported);
+    assert_eq!(result.depth, 0);
+    assert!(result.pv.is_empty());
+    // but there is still a legal move to play
+    assert!(game.apply(result.best_move.unwrap()).is_ok());
+}
+
+#[test]
+fn a_search_can_be_run_again () -> () {
+    let game = Game::new();
+    let mut search = Search::new(Limits { nodes: Some(2000), ..Limits::default() });
+
+    let first = search.run(&game);
+    let second = search.run(&game);
+
+    // running out of nodes the first time does not leave the second run stopped,
+    // with the table filled by the first it gets at least as deep
+    assert!(first.depth > 0);
+    assert!(second.depth >= first.depth, "{} then {}", first.depth, second.depth);
+    assert!(second.nodes >= 2000);
+}
+
+#[test]
+fn time_limit_is_kept () -> () {
+    let limit = Duration::from_millis(300);
+    let result = Search::new(Limits { time: Some(limit), ..Limits::default() }).run(&Game::new());
+
+    assert!(result.best_move.is_some());
+    assert!(result.elapsed < limit + Duration::from_millis(150), "{:?}", result.elapsed);
+}
+
+fn best (fen: &str, limits: Limits) -> (String, Option<i32>, i32) {
+    let result = Game::from_fen(fen).unwrap().search(limits);
+    (result.best_move.unwrap().to_string(), result.mate_in(), result.score)
+}
+
+#[test]
+fn finds_mate_in_one () -> () {
+    // back rank mate with Rd8
+    let (mv, mate, _) = best("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", depth(3));
+    assert_eq!((mv.as_str(), mate), ("d1d8", Some(1)));
+}
+
+#[test]
+fn finds_mate_in_two () -> () {
+    // the queen is given up on d8 so that the rook mates on e8 after Bxd8
+    let (mv, mate, _) = best("r1b2k1r/ppp1bppp/8/1B1Q4/5q2/2P5/PPP2PPP/R3R1K1 w - - 1 1", depth(4));
+    assert_eq!((mv.as_str(), mate), ("d5d8", Some(2)));
+
+    // and from the other side, the mate is seen coming
+    let (_, mate, _) = best("r1bQ1k1r/ppp1bppp/8/1B6/5q2/2P5/PPP2PPP/R3R1K1 b - - 2 1", depth(3));
+    assert_eq!(mate, Some(-1));
+}
+
+#[test]
+fn takes_a_hanging_queen () -> () {
+    // the queen eyes the rook, but nothing guards her on d5
+    let (mv, _, score) = best("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", depth(3));
+    assert_eq!(mv, "d1d5");
+    assert!(score > 300, "{}", score);
+}
+
+#[test]
+fn quiescence_sees_the_recapture () -> () {
+    // Qxd5 wins a pawn at first sight but cxd5 takes the queen back; even at depth 1
+    // the search has to look past the capture and leave the pawn alone
+    let (mv, _, score) = best("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", depth(1));
+    assert_ne!(mv, "d2d5");
+    assert!(score > 300, "{}", score);
+}
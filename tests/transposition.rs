@@ -0,0 +1,107 @@
+#![allow(clippy::unused_unit)]
+
+use chess_rs::piece::{Move, MoveKind, Name, Position};
+use chess_rs::search::MATE;
+use chess_rs::transposition::{Bound, Entry, TranspositionTable};
+
+const KEY: u64 = 0x0123_4567_89AB_CDEF;
+
+fn mv (from: &str, to: &str, promotion: Option<Name>, kind: MoveKind) -> Move {
+    Move {
+        from: Position::from_algebraic(from).unwrap(),
+        to: Position::from_algebraic(to).unwrap(),
+        promotion,
+        kind,
+    }
+}
+
+fn round_trip (table: &TranspositionTable, depth: u32, bound: Bound, score: i32, best_move: Option<Move>) -> Entry {
+    table.clear();
+    table.store(KEY, depth, bound, score, best_move, 0);
+    table.probe(KEY, 0).unwrap()
+}
+
+#[test]
+fn every_field_survives_packing () -> () {
+    let table = TranspositionTable::new(1);
+
+    for bound in [Bound::Exact, Bound::Lower, Bound::Upper] {
+        for score in [0, 1, -1, 250, -250, i16::MAX as i32, i16::MIN as i32] {
+            for depth in [0, 1, 17, 255] {
+                let entry = round_trip(&table, depth, bound, score, None);
+                assert_eq!((entry.depth, entry.bound, entry.score, entry.best_move), (depth, bound, score, None));
+            }
+        }
+    }
+
+    let moves = [
+        mv("a8", "h1", None, MoveKind::Quiet),
+        mv("h1", "a8", None, MoveKind::Capture),
+        mv("e5", "d6", None, MoveKind::EnPassant),
+        mv("e1", "g1", None, MoveKind::Castle),
+        mv("e2", "e4", None, MoveKind::DoublePush),
+        mv("b7", "b8", Some(Name::Queen), MoveKind::Quiet),
+        mv("b7", "a8", Some(Name::Knight), MoveKind::Capture),
+        mv("g2", "g1", Some(Name::Rook), MoveKind::Quiet),
+        mv("g2", "h1", Some(Name::Bishop), MoveKind::Capture),
+    ];
+    for best_move in moves {
+        let entry = round_trip(&table, 3, Bound::Exact, -42, Some(best_move));
+        assert_eq!(entry.best_move, Some(best_move));
+        assert_eq!(entry.score, -42);
+    }
+}
+
+#[test]
+fn age_follows_the_search () -> () {
+    let table = TranspositionTable::new(1);
+    table.store(KEY, 1, Bound::Exact, 0, None, 0);
+    assert_eq!(table.probe(KEY, 0).unwrap().age, 0);
+
+    table.new_search();
+    table.new_search();
+    table.store(KEY, 1, Bound::Exact, 0, None, 0);
+    assert_eq!(table.probe(KEY, 0).unwrap().age, 2);
+
+    // only six bits are kept, the age wraps around
+    for _ in 0..62 {
+        table.new_search();
+    }
+    table.store(KEY, 1, Bound::Exact, 0, None, 0);
+    assert_eq!(table.probe(KEY, 0).unwrap().age, 0);
+}
+
+#[test]
+fn a_different_key_in_the_same_slot_misses () -> () {
+    let table = TranspositionTable::new(1);
+    // the slot index comes from the low bits only
+    let other = KEY ^ (1 << 60);
+
+    table.store(KEY, 4, Bound::Lower, 100, None, 0);
+    assert!(table.probe(KEY, 0).is_some());
+    assert!(table.probe(other, 0).is_none());
+
+    // and the newer position takes the slot over
+    table.store(other, 4, Bound::Lower, 100, None, 0);
+    assert!(table.probe(KEY, 0).is_none());
+    assert!(table.probe(other, 0).is_some());
+}
+
+#[test]
+fn mate_scores_are_relative_to_the_ply () -> () {
+    let table = TranspositionTable::new(1);
+
+    // mating in 5 plies from a node 3 plies into the tree
+    table.store(KEY, 2, Bound::Exact, MATE - 5, None, 3);
+    assert_eq!(table.probe(KEY, 3).unwrap().score, MATE - 5);
+    assert_eq!(table.probe(KEY, 0).unwrap().score, MATE - 2);
+    assert_eq!(table.probe(KEY, 10).unwrap().score, MATE - 12);
+
+    table.store(KEY, 2, Bound::Exact, -MATE + 6, None, 4);
+    assert_eq!(table.probe(KEY, 4).unwrap().score, -MATE + 6);
+    assert_eq!(table.probe(KEY, 1).unwrap().score, -MATE + 3);
+
+    // ordinary scores are left alone
+    table.store(KEY, 2, Bound::Exact, -300, None, 9);
+    assert_eq!(table.probe(KEY, 2).unwrap().score, -300);
+}
@@ -0,0 +1,210 @@
+#![allow(clippy::unused_unit)]
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess_rs::game::Game;
+use chess_rs::piece::Color;
+use chess_rs::uci::{limits, parse_go, Uci};
+
+// back rank mate with Rd8, white to move
+const MATE_IN_ONE: &str = "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1";
+
+// feeds the commands one at a time to a fresh engine and returns everything it said;
+// like a GUI it waits for the bestmove of a go before sending anything else, except
+// for an infinite go which only a later stop ends
+fn session (commands: &[&str]) -> Vec<String> {
+    let mut engine = Uci::new(Vec::new());
+    let out = engine.output();
+    let lines = || -> Vec<String> {
+        let bytes = out.lock().unwrap();
+        String::from_utf8(bytes.clone()).unwrap().lines().map(String::from).collect()
+    };
+
+    for command in commands {
+        let moves_before = bestmoves(&lines()).len();
+        engine.handle(command);
+
+        if command.starts_with("go") && !command.contains("infinite") {
+            let start = Instant::now();
+            while bestmoves(&lines()).len() == moves_before {
+                assert!(start.elapsed() < Duration::from_secs(30), "no bestmove for '{}'", command);
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+    engine.handle("quit");
+
+    lines()
+}
+
+fn bestmoves (lines: &[String]) -> Vec<&str> {
+    lines.iter()
+        .filter_map(|line| line.strip_prefix("bestmove "))
+        .map(|rest| rest.split_whitespace().next().unwrap())
+        .collect()
+}
+
+fn infos (lines: &[String]) -> Vec<&String> {
+    lines.iter().filter(|line| line.starts_with("info depth ")).collect()
+}
+
+// the value following a field of an info line
+fn field<'a> (line: &'a str, name: &str) -> Option<&'a str> {
+    let mut tokens = line.split_whitespace();
+    tokens.find(|&token| token == name)?;
+    tokens.next()
+}
+
+#[test]
+fn handshake () -> () {
+    let lines = session(&["uci", "isready"]);
+
+    assert!(lines[0].starts_with("id name chess-rs"));
+    assert!(lines[1].starts_with("id author"));
+    assert!(lines.iter().any(|line| line.starts_with("option name Hash type spin")));
+    assert!(lines.iter().any(|line| line == "option name Clear Hash type button"));
+    assert!(lines.iter().any(|line| line.starts_with("option name Weights type string")));
+    assert_eq!(&lines[lines.len() - 2..], ["uciok", "readyok"]);
+}
+
+#[test]
+fn go_depth_reports_every_iteration () -> () {
+    let lines = session(&["position startpos moves e2e4 e7e5", "go depth 3"]);
+
+    let infos = infos(&lines);
+    let depths: Vec<&str> = infos.iter().filter_map(|line| field(line, "depth")).collect();
+    assert_eq!(depths, ["1", "2", "3"]);
+    for line in &infos {
+        assert!(field(line, "score") == Some("cp"), "{}", line);
+        assert!(field(line, "nodes").is_some() && field(line, "nps").is_some(), "{}", line);
+        assert!(field(line, "time").is_some() && field(line, "hashfull").is_some(), "{}", line);
+        assert!(field(line, "pv").is_some(), "{}", line);
+    }
+
+    // the move comes last, after the line that shows it, and is legal
+    let best = bestmoves(&lines);
+    assert_eq!(best.len(), 1);
+    assert!(lines.last().unwrap().starts_with("bestmove "));
+    assert_eq!(field(infos[2], "pv"), Some(best[0]));
+
+    let mut game = Game::new();
+    game.apply_uci("e2e4").unwrap();
+    game.apply_uci("e7e5").unwrap();
+    assert!(game.apply_uci(best[0]).is_ok());
+}
+
+#[test]
+fn go_finds_the_mate () -> () {
+    let lines = session(&[&format!("position fen {}", MATE_IN_ONE), "go depth 3"]);
+
+    let infos = infos(&lines);
+    assert!(infos.last().unwrap().contains("score mate 1"), "{:?}", infos);
+    assert_eq!(field(infos.last().unwrap(), "pv"), Some("d1d8"));
+    assert_eq!(lines.last().unwrap(), "bestmove d1d8");
+}
+
+#[test]
+fn go_nodes_movetime_and_clock () -> () {
+    let lines = session(&["position startpos", "go nodes 1000", "go movetime 100",
+                          "position startpos moves g1f3", "go wtime 1000 btime 1000 winc 10 binc 10"]);
+
+    let best = bestmoves(&lines);
+    assert_eq!(best.len(), 3, "{:?}", lines);
+    assert!(Game::new().apply_uci(best[0]).is_ok());
+    assert!(Game::new().apply_uci(best[1]).is_ok());
+
+    let mut game = Game::new();
+    game.apply_uci("g1f3").unwrap();
+    assert!(game.apply_uci(best[2]).is_ok());
+}
+
+#[test]
+fn infinite_go_waits_for_stop () -> () {
+    let mut engine = Uci::new(Vec::new());
+    let out = engine.output();
+    let said = || String::from_utf8(out.lock().unwrap().clone()).unwrap();
+
+    engine.handle("position startpos");
+    engine.handle("go infinite");
+    thread::sleep(Duration::from_millis(100));
+    assert!(!said().contains("bestmove"), "{}", said());
+
+    // stop only returns once the move is out
+    engine.handle("stop");
+    let lines: Vec<String> = said().lines().map(String::from).collect();
+    assert!(!infos(&lines).is_empty());
+    let best = bestmoves(&lines);
+    assert_eq!(best.len(), 1);
+    assert!(Game::new().apply_uci(best[0]).is_ok());
+}
+
+#[test]
+fn options () -> () {
+    let lines = session(&[
+        "setoption name Hash value 16",
+        "setoption name Clear Hash",
+        "setoption name Weights value <empty>",
+        "setoption name hash value lots",
+        "setoption name Weights value /no/such/weights.txt",
+        "setoption name Ponder value true",
+    ]);
+
+    // the good ones are taken silently
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+    assert_eq!(lines[0], "info string bad Hash value 'lots'");
+    assert!(lines[1].starts_with("info string bad Weights file '/no/such/weights.txt'"));
+    assert_eq!(lines[2], "info string unknown option 'Ponder'");
+
+    // a resized table still searches
+    let lines = session(&["setoption name Hash value 1", "position startpos", "go depth 2"]);
+    assert_eq!(bestmoves(&lines).len(), 1);
+}
+
+#[test]
+fn bad_positions_are_reported () -> () {
+    let lines = session(&["position fen not a fen", "position startpos moves e2e4 e2e4", "position"]);
+
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+    assert!(lines[0].starts_with("info string bad position"));
+    assert!(lines[1].starts_with("info string bad move e2e4"));
+    assert!(lines[2].starts_with("info string bad position"));
+}
+
+#[test]
+fn a_bad_position_keeps_the_last_good_one () -> () {
+    // e2e4 goes through before the second e2e4 fails, it must not be kept either
+    let lines = session(&[&format!("position fen {}", MATE_IN_ONE), "position startpos moves e2e4 e2e4", "go depth 2"]);
+
+    assert!(lines[0].starts_with("info string bad move e2e4"));
+    assert_eq!(lines.last().unwrap(), "bestmove d1d8");
+}
+
+#[test]
+fn parsing_go () -> () {
+    let fixed = limits(&parse_go(&["depth", "5", "nodes", "20000", "searchmoves", "e2e4"]), Color::White);
+    assert_eq!(fixed.depth, Some(5));
+    assert_eq!(fixed.nodes, Some(20000));
+    assert_eq!(fixed.time, None);
+
+    // malformed values are dropped
+    let malformed = limits(&parse_go(&["depth", "deep", "nodes"]), Color::White);
+    assert_eq!((malformed.depth, malformed.nodes, malformed.time), (None, None, None));
+}
+
+#[test]
+fn limits_from_the_clock () -> () {
+    let go = parse_go(&["wtime", "60000", "btime", "30000", "winc", "1000", "binc", "0", "movestogo", "10"]);
+
+    // each side's own clock is shared out over the moves to go, most of the increment on top
+    assert_eq!(limits(&go, Color::White).time, Some(Duration::from_millis(6750)));
+    assert_eq!(limits(&go, Color::Black).time, Some(Duration::from_millis(3000)));
+
+    // a movetime wins over the clock, less what it takes the move to get out
+    let go = parse_go(&["movetime", "1000", "wtime", "60000"]);
+    assert_eq!(limits(&go, Color::White).time, Some(Duration::from_millis(950)));
+
+    // an infinite search has no limits at all, whatever else was given
+    let infinite = limits(&parse_go(&["infinite", "depth", "3"]), Color::White);
+    assert_eq!((infinite.depth, infinite.nodes, infinite.time), (None, None, None));
+}
@@ -0,0 +1,160 @@
+#![allow(clippy::unused_unit)]
+
+use std::time::Duration;
+
+use chess_rs::xboard::Xboard;
+
+// back rank mate with Rd8, white to move
+const MATE_IN_ONE: &str = "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1";
+
+// feeds a whole session to a fresh engine and returns everything it said
+fn transcript (input: &str) -> Vec<String> {
+    let mut engine = Xboard::new(Vec::new());
+    engine.run(input.as_bytes()).unwrap();
+
+    let out = engine.output();
+    let bytes = out.lock().unwrap();
+    String::from_utf8(bytes.clone()).unwrap().lines().map(String::from).collect()
+}
+
+fn engine_moves (lines: &[String]) -> Vec<&str> {
+    lines.iter().filter_map(|line| line.strip_prefix("move ")).collect()
+}
+
+#[test]
+fn handshake () -> () {
+    let lines = transcript("xboard\nprotover 2\nping 7\n");
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("feature "));
+    assert!(lines[0].contains("usermove=1") && lines[0].contains("setboard=1") && lines[0].ends_with("done=1"));
+    assert_eq!(lines[1], "pong 7");
+}
+
+#[test]
+fn force_mode_keeps_quiet () -> () {
+    let lines = transcript("new\nforce\nusermove e2e4\nusermove e7e5\nusermove g1f3\n");
+    assert!(lines.is_empty(), "{:?}", lines);
+}
+
+#[test]
+fn illegal_and_unknown_input () -> () {
+    let lines = transcript("new\nforce\nusermove e2e5\nfrobnicate\nsetboard not a fen\nlevel 40 x 0\n");
+
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("Illegal move") && lines[0].ends_with(": e2e5"));
+    assert_eq!(lines[1], "Error (unknown command): frobnicate");
+    assert!(lines[2].starts_with("tellusererror Illegal position"));
+    assert!(lines[3].starts_with("Error (bad level)"));
+}
+
+#[test]
+fn undo_and_remove_follow_the_history () -> () {
+    // after remove white is to move again, so black's move is rejected
+    let lines = transcript("new\nforce\nusermove e2e4\nusermove e7e5\nremove\nusermove e7e5\n");
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("Illegal move"));
+
+    // after undo it is black's move that was taken back
+    let lines = transcript("new\nforce\nusermove e2e4\nusermove e7e5\nundo\nusermove e7e5\n");
+    assert!(lines.is_empty(), "{:?}", lines);
+}
+
+#[test]
+fn go_finds_the_mate () -> () {
+    let lines = transcript(&format!("new\nforce\nsetboard {}\nsd 3\ngo\n", MATE_IN_ONE));
+
+    assert_eq!(lines, ["move d1d8", "1-0 {White mates}"]);
+}
+
+#[test]
+fn engine_answers_as_black () -> () {
+    let lines = transcript("new\nsd 2\nusermove e2e4\nusermove d2d4\n");
+
+    // one reply for each of white's moves, each legal when the next user move arrived
+    assert_eq!(engine_moves(&lines).len(), 2, "{:?}", lines);
+    assert!(!lines.iter().any(|line| line.starts_with("Illegal")), "{:?}", lines);
+}
+
+#[test]
+fn result_ends_the_engine_side () -> () {
+    let lines = transcript("new\nsd 1\nresult 1/2-1/2 {agreed}\nusermove e2e4\n");
+    assert!(lines.is_empty(), "{:?}", lines);
+}
+
+#[test]
+fn post_shows_thinking () -> () {
+    let lines = transcript(&format!("new\nforce\npost\nsetboard {}\nsd 1\ngo\n", MATE_IN_ONE));
+
+    // ply, score, centiseconds, nodes, pv
+    let fields: Vec<&str> = lines[0].split_whitespace().collect();
+    assert_eq!(fields[0], "1");
+    assert_eq!(fields[1], "100001");
+    assert_eq!(fields[4], "d1d8");
+    assert_eq!(&lines[1..], ["move d1d8", "1-0 {White mates}"]);
+}
+
+#[test]
+fn user_mate_is_announced () -> () {
+    let lines = transcript(&format!("new\nforce\nsetboard {}\nusermove d1d8\n", MATE_IN_ONE));
+
+    assert_eq!(lines, ["1-0 {White mates}"]);
+}
+
+// the time the engine would give its next move after the commands, with a fresh engine
+fn allotted (commands: &[&str]) -> Duration {
+    let mut engine = Xboard::new(Vec::new());
+    for command in commands {
+        engine.handle(command);
+    }
+    engine.limits().time.unwrap()
+}
+
+#[test]
+fn level_shares_the_base_time () -> () {
+    // 40 moves in 5 minutes by default, all of it still to come on the first move
+    assert_eq!(allotted(&["new"]), Duration::from_secs(300) / 40);
+    assert_eq!(allotted(&["new", "level 40 5 0"]), Duration::from_secs(300) / 40);
+    // minutes and seconds, and three quarters of the increment on top
+    assert_eq!(allotted(&["new", "level 20 2:30 2"]), Duration::from_millis(150_000 / 20 + 1_500));
+
+    // a sudden death control plans for 30 moves
+    assert_eq!(allotted(&["new", "level 0 10 0"]), Duration::from_secs(600) / 30);
+}
+
+#[test]
+fn level_counts_the_moves_to_go () -> () {
+    let played = ["new", "force", "level 10 5 0", "usermove e2e4", "usermove e7e5", "usermove g1f3", "usermove b8c6"];
+    // two moves into a 10 move control, 8 are left
+    assert_eq!(allotted(&played), Duration::from_secs(300) / 8);
+
+    // once a control is over the count starts again from the full number
+    let played = ["new", "force", "level 2 5 0", "usermove g1f3", "usermove g8f6", "usermove f3g1", "usermove f6g8"];
+    assert_eq!(allotted(&played), Duration::from_secs(300) / 2);
+}
+
+#[test]
+fn time_is_taken_from_the_engine_clock () -> () {
+    // centiseconds left, only the engine's own clock counts
+    assert_eq!(allotted(&["new", "level 40 5 0", "time 6000", "otim 100"]), Duration::from_secs(60) / 40);
+    assert_eq!(allotted(&["new", "level 0 5 1", "time 3000"]), Duration::from_millis(30_000 / 30 + 750));
+
+    // nearly out of time there is still something left to move with
+    assert_eq!(allotted(&["new", "time 3"]), Duration::from_millis(1));
+    // and never more than is on the clock, less what it takes the move to get out
+    assert_eq!(allotted(&["new", "level 1 5 60", "time 1000"]), Duration::from_millis(9_950));
+}
+
+#[test]
+fn st_fixes_the_time_per_move () -> () {
+    // all of it but what it takes the move to get out, whatever the clock says
+    assert_eq!(allotted(&["new", "st 2", "time 100000"]), Duration::from_millis(1_950));
+
+    // a level afterwards goes back to sharing out the clock
+    assert_eq!(allotted(&["new", "st 2", "level 40 5 0"]), Duration::from_secs(300) / 40);
+
+    let mut engine = Xboard::new(Vec::new());
+    engine.handle("sd 4");
+    assert_eq!(engine.limits().depth, Some(4));
+    assert_eq!(engine.limits().nodes, None);
+}
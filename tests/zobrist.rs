@@ -0,0 +1,73 @@
+#![allow(clippy::unused_unit)]
+
+use chess_rs::board::Board;
+use chess_rs::game::Game;
+use chess_rs::piece::{Color, MoveKind};
+use chess_rs::rules::legal_moves;
+
+// castling both ways for both sides, en passant on the first move and promotions with
+// and without capture
+const POSITIONS: &[&str] = &[
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "r3k2r/8/8/2pP4/8/8/1p4P1/R3K2R w KQkq c6 0 1",
+];
+
+fn refreshed (board: &Board) -> u64 {
+    let mut fresh = board.clone();
+    fresh.refresh_hash();
+    fresh.hash()
+}
+
+// every move down to depth, checking the incremental hash after make and after unmake;
+// counting the moves of each kind in seen, promotions last
+fn walk (board: &mut Board, color: Color, depth: u32, seen: &mut [u32; 6]) -> () {
+    if depth == 0 {
+        return;
+    }
+
+    for mv in legal_moves(board, color) {
+        let before = board.hash();
+        let undo = board.make_move(mv.from, mv.to, mv.promotion).unwrap();
+        assert_eq!(board.hash(), refreshed(board), "after {:?}", mv);
+
+        seen[mv.kind as usize] += 1;
+        if mv.promotion.is_some() {
+            seen[5] += 1;
+        }
+        walk(board, color.opposite(), depth - 1, seen);
+
+        board.unmake_move(undo);
+        assert_eq!(board.hash(), before, "after taking back {:?}", mv);
+    }
+}
+
+#[test]
+fn incremental_hash_matches_a_refresh () -> () {
+    let mut seen = [0; 6];
+
+    for fen in POSITIONS {
+        let game = Game::from_fen(fen).unwrap();
+        let mut board = game.board.clone();
+        assert_eq!(board.hash(), refreshed(&board), "{}", fen);
+
+        walk(&mut board, game.get_turn(), 2, &mut seen);
+    }
+
+    assert!(seen[MoveKind::Castle as usize] > 0);
+    assert!(seen[MoveKind::EnPassant as usize] > 0);
+    assert!(seen[MoveKind::DoublePush as usize] > 0);
+    assert!(seen[5] > 0);
+}
+
+#[test]
+fn hashes_stay_the_same () -> () {
+    // keys stored from earlier builds, in a table or a book, have to keep matching
+    for (fen, hash) in [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 0xD9DB_D9B3_C5EC_BB9F),
+        ("r3k2r/8/8/2pP4/8/8/1p4P1/R3K2R w KQkq c6 0 1", 0x6A3C_1F31_9DC5_803D),
+        ("4k3/8/8/8/8/8/8/4K2R b K - 0 1", 0x1021_5A3D_FA1B_A180),
+    ] {
+        assert_eq!(Game::from_fen(fen).unwrap().hash(), hash, "{}", fen);
+    }
+}